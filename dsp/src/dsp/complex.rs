@@ -156,6 +156,33 @@ impl DSPComplex {
         }
     }
 
+    // atan2(y, x) by CORDIC, in the same unit as phase(); error is within the last step,
+    // atan(2^-(iterations - 1)). up to 16 iterations
+    pub fn phase_cordic(&self, iterations: usize) -> i32 {
+        if self.re.0 == 0 && self.im.0 == 0 {
+            return 0;
+        }
+        let (mut x, mut y) = ((self.re.0 as i32) << 14, (self.im.0 as i32) << 14);
+        // rotate into the right half plane; CORDIC converges within +-pi/2
+        let mut angle = 0;
+        if x < 0 {
+            angle = if y >= 0 { 1 << 17 } else { -(1 << 17) };
+            (x, y) = (-x, -y);
+        }
+        for (i, t) in CORDIC_ATAN.iter().take(iterations).enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if y > 0 {
+                (x, y) = (x + dx, y - dy);
+                angle += *t as i32;
+            } else {
+                (x, y) = (x - dx, y + dy);
+                angle -= *t as i32;
+            }
+        }
+        // back into -pi..pi
+        (angle << 14) >> 14
+    }
+
     pub fn norm(&self) -> DSPNum {
         let re = self.re.0 as i32;
         let im = self.im.0 as i32;
@@ -175,25 +202,6 @@ impl DSPComplex {
         let v = slow_sqrt(re * re + im * im);
         DSPNum(v as i16)
     }
-
-    // arr[i] = expi(i/128 * pi).conj()
-    pub fn make_sequential_expi(arr: &mut [DSPComplex; 128]) {
-        arr[0] = DSPComplex::one();
-        arr[64] = DSPComplex::i().conj();
-        for i in 0..6 {
-            arr[1 << i] = COSSIN_TABLE[5 - i].conj();
-        }
-
-        let mut d = 2;
-        for i in 3..128 {
-            if is_power_of_two(i) {
-                d <<= 1;
-            } else {
-                let j = i & !d;
-                arr[i] = arr[j] * arr[d];
-            }
-        }
-    }
 }
 
 impl From<DSPNum> for DSPComplex {
@@ -205,10 +213,6 @@ impl From<DSPNum> for DSPComplex {
     }
 }
 
-const fn is_power_of_two(x: usize) -> bool {
-    x & (x - 1) == 0
-}
-
 // simple sqrt approximation
 const fn fast_sqrt(x: u32) -> u32 {
     if x == 0 {
@@ -253,6 +257,11 @@ const COSSIN_TABLE: [DSPComplex; 16] = [
     DSPComplex::from_i16(16384, 0),
 ];
 
+// atan(2^-i) / (pi / 2) * 2^16
+const CORDIC_ATAN: [u16; 16] = [
+    32768, 19344, 10221, 5188, 2604, 1303, 652, 326, 163, 81, 41, 20, 10, 5, 3, 1,
+];
+
 #[inline]
 fn sincos_(x: u16) -> DSPComplex {
     let mut sc = DSPComplex::from_i16(1 << DSPNum::FIXED_POINT, 0);
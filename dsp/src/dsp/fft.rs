@@ -0,0 +1,96 @@
+use crate::dsp::{window::Window, DSPComplex, DSPNum};
+
+// N-point FFT with its own twiddle table and window
+pub struct FFT<const N: usize> {
+    // twiddle factors laid out per stage:
+    // omegas[b + i] = exp(-pi * i / b) for each half-size b = 1, 2, 4, ..., N/2
+    omegas: [DSPComplex; N],
+    window: [DSPNum; N],
+    window_kind: Window,
+}
+
+impl<const N: usize> FFT<N> {
+    const SIZE_LOG2: u32 = {
+        assert!(N >= 2 && N.is_power_of_two() && N <= 1 << 16);
+        N.trailing_zeros()
+    };
+
+    pub fn new(window: Window) -> Self {
+        let mut omegas = [DSPComplex::zero(); N];
+        omegas[0] = DSPComplex::one(); // unused
+        let mut b = 1;
+        while b < N {
+            for i in 0..b {
+                // theta = 1<<18 represents 2pi
+                omegas[b + i] = DSPComplex::expi(-(((i as i32) << 17) / b as i32));
+            }
+            b <<= 1;
+        }
+
+        let mut s = Self {
+            omegas,
+            window: [DSPNum(0); N],
+            window_kind: window,
+        };
+        s.set_window(window);
+        s
+    }
+
+    pub fn window(&self) -> Window {
+        self.window_kind
+    }
+
+    pub fn set_window(&mut self, window: Window) {
+        window.fill(&mut self.window);
+        self.window_kind = window;
+    }
+
+    // apply window and FFT in-place
+    pub fn process(&self, arr: &mut [DSPComplex; N]) {
+        if self.window_kind != Window::Rectangular {
+            for (x, w) in arr.iter_mut().zip(self.window.iter()) {
+                *x = *x * *w;
+            }
+        }
+        self.fft(arr);
+    }
+
+    /*
+    apply FFT to arr in-place
+
+    result is normalized by 1/N, and DC is moved to arr[N/2]
+    */
+    pub fn fft(&self, arr: &mut [DSPComplex; N]) {
+        let log2 = Self::SIZE_LOG2;
+        for i in 0..N {
+            let j = (i as u32).reverse_bits() as usize >> (32 - log2);
+            if i < j {
+                arr.swap(i, j);
+            }
+        }
+
+        for j in 0..log2 {
+            let b = 1 << j;
+
+            for i in 0..b {
+                let w = unsafe { *self.omegas.get_unchecked(b + i) };
+
+                let i2 = i | b;
+                for i0 in (0..N).step_by(b << 1) {
+                    // div by 2 to normalize
+                    let a = unsafe { *arr.get_unchecked(i0 | i) } >> 1;
+                    let b = (unsafe { arr.get_unchecked(i0 | i2) } * w) >> 1;
+                    unsafe {
+                        *arr.get_unchecked_mut(i0 | i) = a + b;
+                        *arr.get_unchecked_mut(i0 | i2) = a - b;
+                    }
+                }
+            }
+        }
+
+        // fix frequency order
+        for i in 0..N / 2 {
+            arr.swap(i, i | (N / 2));
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use crate::dsp::{window::Window, DSPComplex, DSPNum};
+
+// sample types which can go through FIR filters
+pub trait FirSample: Copy + Default {
+    type Acc: Copy + Default;
+    fn mac(acc: Self::Acc, x: Self, h: DSPNum) -> Self::Acc;
+    fn finish(acc: Self::Acc) -> Self;
+}
+
+#[inline]
+fn unshift_sat(a: i32) -> i16 {
+    ((a + (1 << (DSPNum::FIXED_POINT - 1))) >> DSPNum::FIXED_POINT)
+        .clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+impl FirSample for DSPNum {
+    type Acc = i32;
+    #[inline]
+    fn mac(acc: i32, x: Self, h: DSPNum) -> i32 {
+        acc + x.0 as i32 * h.0 as i32
+    }
+    #[inline]
+    fn finish(acc: i32) -> Self {
+        DSPNum(unshift_sat(acc))
+    }
+}
+
+impl FirSample for DSPComplex {
+    type Acc = (i32, i32);
+    #[inline]
+    fn mac(acc: (i32, i32), x: Self, h: DSPNum) -> (i32, i32) {
+        (
+            acc.0 + x.re.0 as i32 * h.0 as i32,
+            acc.1 + x.im.0 as i32 * h.0 as i32,
+        )
+    }
+    #[inline]
+    fn finish(acc: (i32, i32)) -> Self {
+        DSPComplex::from_i16(unshift_sat(acc.0), unshift_sat(acc.1))
+    }
+}
+
+/*
+polyphase decimating FIR filter
+
+TAPS coefficients are split into R branches of TAPS / R taps each:
+branch p holds h[k * R + p] and sees every R-th input sample, so only the outputs
+that survive decimation are ever computed.
+*/
+pub struct FirDecimator<S: FirSample, const TAPS: usize, const R: usize> {
+    // taps[p * L + k] = h[k * R + p], L = TAPS / R
+    taps: [DSPNum; TAPS],
+    // delay[p * L + j]: circular delay line of branch p
+    delay: [S; TAPS],
+    // last written position in delay lines
+    pos: usize,
+    // branch which receives next input
+    branch: usize,
+}
+
+// plain FIR filter without decimation
+pub type Fir<S, const TAPS: usize> = FirDecimator<S, TAPS, 1>;
+
+impl<const TAPS: usize, const R: usize> FirDecimator<DSPComplex, TAPS, R> {
+    // multiply the delay lines by w, as if the past input had been so
+    pub fn rotate(&mut self, w: DSPComplex) {
+        for x in self.delay.iter_mut() {
+            *x *= w;
+        }
+    }
+}
+
+impl<S: FirSample, const TAPS: usize, const R: usize> FirDecimator<S, TAPS, R> {
+    const BRANCH_LEN: usize = {
+        assert!(R > 0 && TAPS / R * R == TAPS);
+        TAPS / R
+    };
+
+    pub fn new(h: &[DSPNum; TAPS]) -> Self {
+        let mut s = Self {
+            taps: [DSPNum(0); TAPS],
+            delay: [S::default(); TAPS],
+            pos: 0,
+            branch: R - 1,
+        };
+        s.set_taps(h);
+        s
+    }
+
+    // lowpass with cutoff frequency [Hz] at the input sample rate [Hz]
+    pub fn lowpass(cutoff: u32, rate: u32, window: Window) -> Self {
+        let mut h = [DSPNum(0); TAPS];
+        design_lowpass(&mut h, cutoff, rate, window);
+        Self::new(&h)
+    }
+
+    // change coefficients; delay lines are kept
+    pub fn set_taps(&mut self, h: &[DSPNum; TAPS]) {
+        let l = Self::BRANCH_LEN;
+        for (i, &v) in h.iter().enumerate() {
+            self.taps[(i % R) * l + i / R] = v;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.delay = [S::default(); TAPS];
+        self.pos = 0;
+        self.branch = R - 1;
+    }
+
+    // feed one sample; returns an output every R inputs
+    #[inline]
+    pub fn push(&mut self, x: S) -> Option<S> {
+        let l = Self::BRANCH_LEN;
+        if self.branch == R - 1 {
+            // new output period: advance all delay lines
+            self.pos = if self.pos + 1 == l { 0 } else { self.pos + 1 };
+        }
+        self.delay[self.branch * l + self.pos] = x;
+
+        if self.branch != 0 {
+            self.branch -= 1;
+            return None;
+        }
+        self.branch = R - 1;
+
+        let mut acc = S::Acc::default();
+        for p in 0..R {
+            acc = dot(
+                acc,
+                &self.delay[p * l..(p + 1) * l],
+                &self.taps[p * l..(p + 1) * l],
+                self.pos,
+            );
+        }
+        Some(S::finish(acc))
+    }
+
+    // filter a block; input length should be multiple of R
+    // returns number of written outputs
+    pub fn process(&mut self, input: &[S], output: &mut [S]) -> usize {
+        let mut k = 0;
+        for x in input {
+            if let Some(y) = self.push(*x) {
+                output[k] = y;
+                k += 1;
+            }
+        }
+        k
+    }
+}
+
+// dot product of a circular delay line and taps
+// newest sample at line[pos] pairs with taps[0]
+#[inline]
+fn dot<S: FirSample>(mut acc: S::Acc, line: &[S], taps: &[DSPNum], pos: usize) -> S::Acc {
+    let (older, newer) = line.split_at(pos + 1);
+    let (t_newer, t_older) = taps.split_at(pos + 1);
+    for (x, h) in older.iter().rev().zip(t_newer.iter()) {
+        acc = S::mac(acc, *x, *h);
+    }
+    for (x, h) in newer.iter().rev().zip(t_older.iter()) {
+        acc = S::mac(acc, *x, *h);
+    }
+    acc
+}
+
+/*
+polyphase interpolating FIR filter
+
+every input produces R outputs; output p of each period is computed by branch p
+(h[k * R + p]) over the input history, so stuffed zeros are never multiplied.
+gain is compensated by R.
+*/
+pub struct FirInterpolator<S: FirSample, const TAPS: usize, const R: usize> {
+    // taps[p * L + k] = h[k * R + p] * R, L = TAPS / R
+    taps: [DSPNum; TAPS],
+    // circular input history; only first L entries are used
+    delay: [S; TAPS],
+    pos: usize,
+}
+
+impl<S: FirSample, const TAPS: usize, const R: usize> FirInterpolator<S, TAPS, R> {
+    const BRANCH_LEN: usize = {
+        assert!(R > 0 && TAPS / R * R == TAPS);
+        TAPS / R
+    };
+
+    pub fn new(h: &[DSPNum; TAPS]) -> Self {
+        let mut s = Self {
+            taps: [DSPNum(0); TAPS],
+            delay: [S::default(); TAPS],
+            pos: 0,
+        };
+        s.set_taps(h);
+        s
+    }
+
+    // lowpass with cutoff frequency [Hz] at the output sample rate [Hz]
+    pub fn lowpass(cutoff: u32, rate: u32, window: Window) -> Self {
+        let mut h = [DSPNum(0); TAPS];
+        design_lowpass(&mut h, cutoff, rate, window);
+        Self::new(&h)
+    }
+
+    pub fn set_taps(&mut self, h: &[DSPNum; TAPS]) {
+        let l = Self::BRANCH_LEN;
+        for (i, &v) in h.iter().enumerate() {
+            self.taps[(i % R) * l + i / R] = DSPNum(v.0 * R as i16);
+        }
+    }
+
+    // feed one sample and get R outputs
+    #[inline]
+    pub fn push(&mut self, x: S, output: &mut [S]) {
+        let l = Self::BRANCH_LEN;
+        self.pos = if self.pos + 1 == l { 0 } else { self.pos + 1 };
+        self.delay[self.pos] = x;
+
+        for (p, o) in output.iter_mut().enumerate().take(R) {
+            let acc = dot(
+                S::Acc::default(),
+                &self.delay[..l],
+                &self.taps[p * l..(p + 1) * l],
+                self.pos,
+            );
+            *o = S::finish(acc);
+        }
+    }
+}
+
+/*
+windowed-sinc lowpass design
+
+h is normalized to unity DC gain. cutoff is -6dB point.
+*/
+pub fn design_lowpass(h: &mut [DSPNum], cutoff: u32, rate: u32, window: Window) {
+    let len = h.len();
+    // unnormalized tap with extra 14bit precision
+    let raw = |n: usize| -> i64 {
+        // compute first half only, to get exactly symmetric taps
+        let n = n.min(len - 1 - n);
+        // twice the distance from the center, to keep even length symmetric
+        let m2 = 2 * n as i64 - (len as i64 - 1);
+        let s = if m2 == 0 {
+            // limit of sin(wm)/m: w = 2pi * cutoff / rate
+            (102944 << DSPNum::FIXED_POINT) * cutoff as i64 / rate as i64
+        } else {
+            // theta = 1<<18 represents 2pi
+            let theta = ((cutoff as i64 * m2) << 17) / rate as i64;
+            ((2 * DSPComplex::expi(theta as i32).im.0 as i64) << DSPNum::FIXED_POINT) / m2
+        };
+        // symmetric window without zero ends
+        s * window.at(n + 1, len + 1).0 as i64
+    };
+
+    let sum: i64 = (0..len).map(raw).sum();
+    if sum == 0 {
+        return;
+    }
+    for (n, v) in h.iter_mut().enumerate() {
+        let x = raw(n) << DSPNum::FIXED_POINT;
+        v.0 = ((x + sum / 2).div_euclid(sum)) as i16;
+    }
+}
+
+/*
+windowed Hilbert transformer design
+
+len of h should be odd; delay is (len - 1) / 2 samples. cos is turned into sin.
+*/
+pub fn design_hilbert(h: &mut [DSPNum], window: Window) {
+    let len = h.len();
+    let c = (len / 2) as i32;
+    for (n, v) in h.iter_mut().enumerate() {
+        let m = n as i32 - c;
+        v.0 = if m % 2 == 0 {
+            0
+        } else {
+            // 2 / (pi * m); compute first half only, to keep exactly antisymmetric
+            let w = window.at(n.min(len - 1 - n) + 1, len + 1).0 as i32;
+            let x = (20861 / m.abs()) * w;
+            let v = ((x + (1 << DSPNum::FIXED_POINT)) >> (DSPNum::FIXED_POINT + 1)) as i16;
+            if m < 0 {
+                -v
+            } else {
+                v
+            }
+        };
+    }
+}
@@ -0,0 +1,101 @@
+use crate::dsp::DSPNum;
+
+// longest decorrelation delay [samples]
+pub const MAX_DELAY: usize = 16;
+
+/*
+normalized LMS adaptive predictor
+
+predicts x[n] from x[n - delay - k], k < TAPS. what is correlated over the delay (tones,
+voice) is predicted and white noise is not, so the prediction is the signal without noise,
+and the error x[n] - prediction is the noise without tones.
+*/
+pub struct Lms<const TAPS: usize> {
+    // Q14, for filtering
+    w: [DSPNum; TAPS],
+    // the same << 16, to accumulate small updates
+    w_acc: [i32; TAPS],
+    // hist[k] = x[n - delay - k]
+    hist: [DSPNum; TAPS],
+    // sum of hist[k]^2 >> 8
+    power: i32,
+    delay: [DSPNum; MAX_DELAY],
+    delay_len: usize,
+    delay_pos: usize,
+    // Q16
+    step: u32,
+    // weights decay by 2^-leak per sample; 0 is none
+    leak: u32,
+}
+
+impl<const TAPS: usize> Lms<TAPS> {
+    // step: adaptation rate, Q16
+    pub fn new(delay: usize, step: u32, leak: u32) -> Self {
+        Self {
+            w: [DSPNum(0); TAPS],
+            w_acc: [0; TAPS],
+            hist: [DSPNum(0); TAPS],
+            power: 0,
+            delay: [DSPNum(0); MAX_DELAY],
+            delay_len: delay.clamp(1, MAX_DELAY),
+            delay_pos: 0,
+            step,
+            leak,
+        }
+    }
+
+    pub fn set_step(&mut self, step: u32) {
+        self.step = step;
+    }
+
+    pub fn set_leak(&mut self, leak: u32) {
+        self.leak = leak;
+    }
+
+    pub fn reset(&mut self) {
+        self.w = [DSPNum(0); TAPS];
+        self.w_acc = [0; TAPS];
+    }
+
+    // returns the prediction of x, then adapts to it
+    pub fn push(&mut self, x: DSPNum) -> DSPNum {
+        let mut acc = 0i32;
+        for (w, h) in self.w.iter().zip(self.hist.iter()) {
+            acc += w.0 as i32 * h.0 as i32;
+        }
+        let y = ((acc + (1 << (DSPNum::FIXED_POINT - 1))) >> DSPNum::FIXED_POINT)
+            .clamp(i16::MIN as i32, i16::MAX as i32);
+        let e = x.0 as i32 - y;
+
+        // w += step * e * h / power, in units of w_acc
+        let c = (((self.step as i64 * e as i64) << 6) / (self.power as i64 + (1 << 8)))
+            .clamp(-(1 << 15), 1 << 15) as i32;
+        for ((w, wa), h) in self
+            .w
+            .iter_mut()
+            .zip(self.w_acc.iter_mut())
+            .zip(self.hist.iter())
+        {
+            if self.leak > 0 {
+                *wa -= *wa >> self.leak;
+            }
+            *wa = wa.saturating_add(c * h.0 as i32);
+            *w = DSPNum((*wa >> 16) as i16);
+        }
+
+        // x goes through the delay, then into the history
+        let d = core::mem::replace(&mut self.delay[self.delay_pos], x);
+        self.delay_pos = if self.delay_pos + 1 == self.delay_len {
+            0
+        } else {
+            self.delay_pos + 1
+        };
+        let old = self.hist[TAPS - 1].0 as i32;
+        self.power -= (old * old) >> 8;
+        self.hist.copy_within(0..TAPS - 1, 1);
+        self.hist[0] = d;
+        self.power += (d.0 as i32 * d.0 as i32) >> 8;
+
+        DSPNum(y as i16)
+    }
+}
@@ -0,0 +1,11 @@
+mod complex;
+pub mod fft;
+pub mod fir;
+pub mod lms;
+pub mod notch;
+mod number;
+pub mod pll;
+pub mod window;
+
+pub use complex::DSPComplex;
+pub use number::{db10, DSPNum};
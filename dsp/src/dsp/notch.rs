@@ -0,0 +1,77 @@
+use crate::dsp::{lms::Lms, DSPComplex, DSPNum};
+
+/*
+second order IIR notch
+
+zeros on the unit circle at freq, poles just inside at radius r; the closer r is to 1,
+the narrower the notch. gain is 1 at DC and nyquist. outputs are kept << 8, as the poles
+are close to the unit circle and would amplify the rounding of a Q14 state.
+*/
+pub struct Notch {
+    // Q14; b0 = b2, a0 = 1
+    b0: i32,
+    b1: i32,
+    a1: i32,
+    a2: i32,
+    x: [i32; 2],
+    // << 8
+    y: [i32; 2],
+}
+
+impl Notch {
+    // freq and width (-3dB) [Hz]
+    pub fn new(freq: u32, width: u32, rate: u32) -> Self {
+        // theta = 1<<18 represents 2pi
+        let c = DSPComplex::expi(((freq as i64) << 18).div_euclid(rate as i64) as i32)
+            .re
+            .0 as i32;
+        // r = 1 - pi width / rate, Q14
+        let r = (1 << 14) - ((51472 * width as i64) / rate as i64) as i32;
+        let a2 = (r * r) >> 14;
+        let g = ((1 << 14) + a2) / 2;
+        Self {
+            b0: g,
+            b1: -((2 * c * g) >> 14),
+            a1: -((2 * r * c) >> 14),
+            a2,
+            x: [0; 2],
+            y: [0; 2],
+        }
+    }
+
+    pub fn push(&mut self, x: DSPNum) -> DSPNum {
+        let x = x.0 as i32;
+        let acc = ((self.b0 as i64 * (x + self.x[1]) as i64 + self.b1 as i64 * self.x[0] as i64)
+            << 8)
+            - self.a1 as i64 * self.y[0] as i64
+            - self.a2 as i64 * self.y[1] as i64;
+        let y = (acc >> DSPNum::FIXED_POINT) as i32;
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        DSPNum((y >> 8).clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+    }
+}
+
+/*
+adaptive notch
+
+the error of Lms: tones, which are predictable over the delay, are taken away from
+the input, while what changes faster, like voice, mostly remains.
+*/
+pub struct AutoNotch<const TAPS: usize> {
+    lms: Lms<TAPS>,
+}
+
+impl<const TAPS: usize> AutoNotch<TAPS> {
+    // see Lms::new
+    pub fn new(delay: usize, step: u32) -> Self {
+        Self {
+            lms: Lms::new(delay, step, 0),
+        }
+    }
+
+    pub fn push(&mut self, x: DSPNum) -> DSPNum {
+        let y = self.lms.push(x);
+        DSPNum(x.0.saturating_sub(y.0))
+    }
+}
@@ -47,3 +47,20 @@ impl DSPNum {
 pub fn unshift_fpmul(a: i32) -> i16 {
     ((a + (1 << (DSPNum::FIXED_POINT - 1))) >> DSPNum::FIXED_POINT) as i16
 }
+
+// 10 log10(x) in 1/256 dB; log2 is interpolated with a parabolic correction
+pub fn db10(x: u64) -> i32 {
+    if x == 0 {
+        return i32::MIN;
+    }
+    let n = 63 - x.leading_zeros() as i32;
+    // fraction of the mantissa, Q8
+    let f = if n >= 8 {
+        ((x >> (n - 8)) & 0xff) as i32
+    } else {
+        ((x << (8 - n)) & 0xff) as i32
+    };
+    let f = f + ((f * (256 - f) * 89) >> 16);
+    // log10(2) * 10 = 3.0103
+    (((n << 8) + f) * 771) >> 8
+}
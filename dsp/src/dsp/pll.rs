@@ -0,0 +1,83 @@
+use crate::dsp::DSPComplex;
+
+/*
+second order phase locked loop
+
+tracks the phase of a complex carrier near DC with a PI loop filter (zeta = 0.707).
+phase is kept in u32 where 1<<32 represents 2pi; frequency accumulator has extra 16bit.
+*/
+pub struct Pll {
+    phase: u32,
+    // phase increment per sample << 16
+    freq: i64,
+    limit: i64,
+    kp: i32,
+    // << 16
+    ki: i64,
+    rate: u32,
+    // averaged cos of phase error, Q14
+    lock: i32,
+}
+
+impl Pll {
+    // averaged phase error below about 37deg
+    const LOCK_THRESHOLD: i32 = 13_107;
+
+    // bandwidth: natural frequency of the loop [Hz]
+    // limit: max frequency offset to track [Hz]
+    pub fn new(bandwidth: u32, limit: u32, rate: u32) -> Self {
+        let bw = bandwidth as i64;
+        let rate_ = rate as i64;
+        Self {
+            phase: 0,
+            freq: 0,
+            // 1<<32 per 2pi, error in Q14
+            // kp = 2 * zeta * wn, ki = wn^2 in radian units
+            kp: (bw * 370_728 / rate_) as i32,
+            ki: ((bw * bw * 1_647_099) << 16) / (rate_ * rate_),
+            limit: ((limit as i64) << 48) / rate_,
+            rate,
+            lock: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0;
+        self.freq = 0;
+        self.lock = 0;
+    }
+
+    // current phase; 1<<18 represents 2pi
+    pub fn phase(&self) -> i32 {
+        (self.phase >> 14) as i32
+    }
+
+    // tracked frequency offset [Hz]
+    pub fn freq(&self) -> i32 {
+        (((self.freq >> 16) * self.rate as i64) >> 32) as i32
+    }
+
+    pub fn locked(&self) -> bool {
+        self.lock > Self::LOCK_THRESHOLD
+    }
+
+    // rotate x by the loop phase, update the loop, and returns rotated x
+    // when locked, the carrier lies on the positive real axis
+    #[inline]
+    pub fn track(&mut self, x: DSPComplex) -> DSPComplex {
+        let y = x * DSPComplex::expi(-self.phase());
+
+        // normalized sin / cos of the phase error
+        let a = (y.fast_abs().0 as i32).max(16);
+        let s = (((y.im.0 as i32) << 14) / a).clamp(-(1 << 14), 1 << 14);
+        let c = (((y.re.0 as i32) << 14) / a).clamp(-(1 << 14), 1 << 14);
+        self.lock += (c - self.lock) >> 8;
+
+        self.freq = (self.freq + self.ki * s as i64).clamp(-self.limit, self.limit);
+        self.phase = self
+            .phase
+            .wrapping_add((self.freq >> 16) as u32)
+            .wrapping_add((self.kp * s) as u32);
+        y
+    }
+}
@@ -0,0 +1,71 @@
+use crate::dsp::{DSPComplex, DSPNum};
+
+// window functions for spectrum analysis
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    BlackmanHarris,
+    FlatTop,
+}
+
+// cosine-sum coefficients in Q14: w(n) = a0 - a1 cos(x) + a2 cos(2x) - a3 cos(3x) + ...
+const HANN: [i32; 2] = [8192, 8192]; // 0.5, 0.5
+const BLACKMAN_HARRIS: [i32; 4] = [5878, 8000, 2315, 191]; // 0.35875, 0.48829, 0.14128, 0.01168
+const FLAT_TOP: [i32; 5] = [3532, 6826, 4543, 1369, 114]; // 0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368
+
+impl Window {
+    pub const ALL: [Window; 4] = [
+        Window::Rectangular,
+        Window::Hann,
+        Window::BlackmanHarris,
+        Window::FlatTop,
+    ];
+
+    pub fn name(&self) -> &'static [u8] {
+        match self {
+            Window::Rectangular => b"RECT",
+            Window::Hann => b"HANN",
+            Window::BlackmanHarris => b"B-H ",
+            Window::FlatTop => b"FLAT",
+        }
+    }
+
+    fn coeffs(&self) -> &'static [i32] {
+        match self {
+            Window::Rectangular => &[1 << DSPNum::FIXED_POINT],
+            Window::Hann => &HANN,
+            Window::BlackmanHarris => &BLACKMAN_HARRIS,
+            Window::FlatTop => &FLAT_TOP,
+        }
+    }
+
+    // n-th value of the periodic window of length len
+    pub fn at(&self, n: usize, len: usize) -> DSPNum {
+        let mut acc: i32 = 0;
+        for (k, a) in self.coeffs().iter().enumerate() {
+            // theta = 1<<18 represents 2pi
+            let theta = ((k * n) as i64) << 18;
+            let c = DSPComplex::expi((theta / len as i64) as i32).re.0 as i32;
+            let v = if k == 0 {
+                *a << DSPNum::FIXED_POINT
+            } else {
+                a * c
+            };
+            if k % 2 == 0 {
+                acc += v;
+            } else {
+                acc -= v;
+            }
+        }
+        DSPNum(((acc + (1 << (DSPNum::FIXED_POINT - 1))) >> DSPNum::FIXED_POINT) as i16)
+    }
+
+    // fill buf with the window of length buf.len()
+    pub fn fill(&self, buf: &mut [DSPNum]) {
+        let len = buf.len();
+        for (n, w) in buf.iter_mut().enumerate() {
+            *w = self.at(n, len);
+        }
+    }
+}
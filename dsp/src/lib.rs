@@ -0,0 +1,6 @@
+#![no_std]
+
+pub const SAMPLE_RATE: usize = 192_000;
+
+pub mod dsp;
+pub mod sdr;
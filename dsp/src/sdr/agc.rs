@@ -0,0 +1,145 @@
+use crate::dsp::{db10, DSPComplex};
+
+use super::DS_RATE;
+
+// output level; 0.5 in Q14
+const TARGET: i32 = 1 << 13;
+// 40dB, Q8
+const MAX_GAIN: i32 = 100 << 8;
+
+// time constants [ms]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct AgcParams {
+    pub attack: u32,
+    pub hang: u32,
+    pub decay: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum AgcPreset {
+    Off,
+    Slow,
+    Fast,
+}
+
+impl AgcPreset {
+    pub const ALL: [AgcPreset; 3] = [AgcPreset::Off, AgcPreset::Slow, AgcPreset::Fast];
+
+    pub fn params(&self) -> Option<AgcParams> {
+        match self {
+            AgcPreset::Off => None,
+            AgcPreset::Slow => Some(AgcParams {
+                attack: 5,
+                hang: 500,
+                decay: 500,
+            }),
+            AgcPreset::Fast => Some(AgcParams {
+                attack: 2,
+                hang: 100,
+                decay: 100,
+            }),
+        }
+    }
+}
+
+/*
+audio AGC
+
+follows the peak envelope of the demodulated audio; rises in `attack`, holds for `hang`
+after the last peak, then falls in `decay`. gain brings the envelope to TARGET, up to
+MAX_GAIN. when off, audio passes through as is.
+*/
+pub struct Agc {
+    params: Option<AgcParams>,
+    // Q24 per sample
+    attack: i32,
+    decay: i32,
+    // samples
+    hang: u32,
+
+    // Q14 << 8
+    env: i32,
+    hang_count: u32,
+    // Q8
+    gain: i32,
+}
+
+impl Agc {
+    pub fn new(params: Option<AgcParams>) -> Self {
+        let mut s = Self {
+            params: None,
+            attack: 0,
+            decay: 0,
+            hang: 0,
+            env: (TARGET << 16) / MAX_GAIN,
+            hang_count: 0,
+            gain: 1 << 8,
+        };
+        s.set_params(params);
+        s
+    }
+
+    pub fn params(&self) -> Option<AgcParams> {
+        self.params
+    }
+
+    pub fn set_params(&mut self, params: Option<AgcParams>) {
+        self.params = params;
+        let Some(p) = params else {
+            self.gain = 1 << 8;
+            return;
+        };
+        // 1 - exp(-1 / (rate * tau)) ~= 1 / (rate * tau)
+        let coef = |ms: u32| ((1 << 24) / (ms * DS_RATE as u32 / 1000).max(1)) as i32;
+        self.attack = coef(p.attack);
+        self.decay = coef(p.decay);
+        self.hang = p.hang * DS_RATE as u32 / 1000;
+    }
+
+    // current gain [Q8 dB]
+    pub fn gain_db(&self) -> i32 {
+        db10((self.gain as u64) * (self.gain as u64)) - db10(1 << 16)
+    }
+
+    // re only, or both of re and im if `stereo`
+    pub fn process(&mut self, buf: &mut [DSPComplex], stereo: bool) {
+        if self.params.is_none() {
+            return;
+        }
+        for x in buf.iter_mut() {
+            let a = if stereo {
+                x.re.0.unsigned_abs().max(x.im.0.unsigned_abs())
+            } else {
+                x.re.0.unsigned_abs()
+            } as i32;
+            let a = a << 8;
+
+            if a > self.env {
+                self.env += (((a - self.env) as i64 * self.attack as i64) >> 24) as i32;
+                self.hang_count = self.hang;
+            } else if self.hang_count > 0 {
+                self.hang_count -= 1;
+            } else {
+                let d = ((self.env as i64 * self.decay as i64) >> 24) as i32;
+                self.env = (self.env - d.max(1)).max(0);
+            }
+
+            self.gain =
+                (((TARGET as i64) << 16) / self.env.max(1) as i64).min(MAX_GAIN as i64) as i32;
+
+            let g = |v: i16| {
+                ((v as i32 * self.gain) >> 8).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+            };
+            x.re.0 = g(x.re.0);
+            if stereo {
+                x.im.0 = g(x.im.0);
+            }
+        }
+    }
+}
+
+impl Default for Agc {
+    fn default() -> Self {
+        Self::new(AgcPreset::Slow.params())
+    }
+}
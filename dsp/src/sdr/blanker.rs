@@ -0,0 +1,148 @@
+use crate::dsp::DSPComplex;
+use crate::SAMPLE_RATE;
+
+use super::shift::Shifter;
+
+/*
+IQ noise blanker
+
+works on the raw input at SAMPLE_RATE, ahead of Shifter. a sample whose magnitude is more than
+`threshold` times the running average is taken as an impulse, and `width` around it is replaced
+by a line between the good samples on both sides. the average follows the level slowly, and
+only up to the threshold, so that impulses don't raise it. it starts from the mean of the first
+block after turning on.
+*/
+pub struct NoiseBlanker {
+    threshold: Option<u32>,
+    // samples
+    width: usize,
+
+    // average magnitude << 12; None until the first block
+    avg: Option<i64>,
+    // samples left to blank from the previous block
+    hold: usize,
+    // last output sample of the previous block
+    last: DSPComplex,
+}
+
+// average over 1 << AVG_SHIFT samples, about 5ms
+const AVG_SHIFT: u32 = 10;
+
+impl NoiseBlanker {
+    pub const MIN_THRESHOLD: u32 = 3;
+    pub const MAX_THRESHOLD: u32 = 20;
+    pub const DEFAULT_THRESHOLD: u32 = 8;
+    // [us]
+    pub const MIN_WIDTH: u32 = 10;
+    pub const MAX_WIDTH: u32 = 500;
+    pub const DEFAULT_WIDTH: u32 = 50;
+
+    pub fn new() -> Self {
+        let mut s = Self {
+            threshold: None,
+            width: 0,
+            avg: None,
+            hold: 0,
+            last: DSPComplex::zero(),
+        };
+        s.set_width(Self::DEFAULT_WIDTH);
+        s
+    }
+
+    pub fn threshold(&self) -> Option<u32> {
+        self.threshold
+    }
+
+    // ratio to the average magnitude; None is off
+    pub fn set_threshold(&mut self, threshold: Option<u32>) {
+        if threshold.is_some() != self.threshold.is_some() {
+            // the average is stale after being off
+            self.reset();
+        }
+        self.threshold = threshold.map(|t| t.clamp(Self::MIN_THRESHOLD, Self::MAX_THRESHOLD));
+    }
+
+    pub fn reset(&mut self) {
+        self.avg = None;
+        self.hold = 0;
+        self.last = DSPComplex::zero();
+    }
+
+    // [us]
+    pub fn width(&self) -> u32 {
+        (self.width as u64 * 1_000_000 / SAMPLE_RATE as u64) as u32
+    }
+
+    // blanked span around an impulse [us]
+    pub fn set_width(&mut self, width: u32) {
+        let w = width.clamp(Self::MIN_WIDTH, Self::MAX_WIDTH) as u64;
+        self.width = ((w * SAMPLE_RATE as u64 / 1_000_000) as usize).max(1);
+    }
+
+    // buf: up to Shifter::INPUT_SIZE samples
+    pub fn process(&mut self, buf: &mut [DSPComplex]) {
+        let Some(threshold) = self.threshold else {
+            return;
+        };
+        let len = buf.len();
+        let mut blank = [false; Shifter::INPUT_SIZE];
+        let blank = &mut blank[..len];
+
+        // carried over from the previous block
+        let mut end = self.hold.min(len);
+        self.hold -= end;
+        blank[..end].fill(true);
+
+        let mag = |x: &DSPComplex| (x.fast_abs().0 as i64) << 12;
+        let mut avg = self
+            .avg
+            .unwrap_or_else(|| buf.iter().map(mag).sum::<i64>() / len.max(1) as i64);
+        for (i, x) in buf.iter().enumerate() {
+            let m = mag(x);
+            let limit = avg * threshold as i64;
+            if m > limit {
+                let from = i.saturating_sub(self.width / 2);
+                let to = i + self.width - self.width / 2;
+                if to > len {
+                    self.hold = self.hold.max(to - len);
+                }
+                end = end.max(to.min(len));
+                blank[from..end].fill(true);
+            }
+            // impulses are counted only up to the limit; +1 lets it start from zero
+            avg += (m.min(limit + (1 << 12)) - avg) >> AVG_SHIFT;
+        }
+        self.avg = Some(avg);
+
+        // interpolate over each blanked run
+        let mut i = 0;
+        while i < len {
+            if !blank[i] {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < len && blank[i] {
+                i += 1;
+            }
+            let a = if start > 0 { buf[start - 1] } else { self.last };
+            // hold at the end of the block
+            let b = if i < len { buf[i] } else { a };
+            let n = (i - start + 1) as i32;
+            for (k, x) in buf[start..i].iter_mut().enumerate() {
+                let k = k as i32 + 1;
+                let lerp = |a: i16, b: i16| (a as i32 + (b as i32 - a as i32) * k / n) as i16;
+                *x = DSPComplex::from_i16(lerp(a.re.0, b.re.0), lerp(a.im.0, b.im.0));
+            }
+        }
+        if let Some(x) = buf.last() {
+            self.last = *x;
+        }
+    }
+}
+
+impl Default for NoiseBlanker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
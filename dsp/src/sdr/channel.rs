@@ -0,0 +1,180 @@
+use crate::dsp::{
+    notch::{AutoNotch, Notch},
+    DSPComplex, DSPNum,
+};
+
+use super::{
+    agc::Agc,
+    demod::{DemodMethod, Demodulators},
+    nr::NoiseReduction,
+    shift::Shifter,
+    squelch::Squelch,
+    DS_RATE,
+};
+
+// -3dB width of the manual notch [Hz]
+const NOTCH_WIDTH: u32 = 100;
+// automatic notch; the delay is longer than voice stays predictable
+const ANF_TAPS: usize = 32;
+const ANF_DELAY: usize = 16;
+const ANF_STEP: u32 = 1 << 10;
+
+/*
+one receive channel: Shifter, demodulator, noise reduction, notches, squelch and AGC
+
+several of them can run on the same input, each at its own offset within the span.
+*/
+pub struct Channel {
+    pub demods: Demodulators,
+    pub nr: NoiseReduction,
+    pub squelch: Squelch,
+    pub agc: Agc,
+    shifter: Shifter,
+    notch: Option<Notch>,
+    notch_freq: Option<u32>,
+    auto_notch: Option<AutoNotch<ANF_TAPS>>,
+    // offset from the center of the input [Hz]
+    freq: i32,
+    // wideband methods work in place
+    buf: [DSPComplex; Shifter::INPUT_SIZE],
+}
+
+impl Channel {
+    // range of the manual notch [Hz]
+    pub const MIN_NOTCH: u32 = 200;
+    pub const MAX_NOTCH: u32 = 4_000;
+
+    pub fn new() -> Self {
+        let mut s = Self {
+            demods: Demodulators::new(),
+            nr: NoiseReduction::new(),
+            squelch: Squelch::new(),
+            agc: Agc::new(None),
+            shifter: Shifter::new(),
+            notch: None,
+            notch_freq: None,
+            auto_notch: None,
+            freq: 0,
+            buf: [DSPComplex::zero(); Shifter::INPUT_SIZE],
+        };
+        s.configure();
+        s
+    }
+
+    pub fn freq(&self) -> i32 {
+        self.freq
+    }
+
+    pub fn set_freq(&mut self, freq: i32) {
+        self.freq = freq;
+        self.configure();
+    }
+
+    pub fn method(&self) -> DemodMethod {
+        self.demods.method()
+    }
+
+    pub fn set_method(&mut self, method: DemodMethod) {
+        self.demods.set_method(method);
+        self.demods.current().reset();
+        self.configure();
+    }
+
+    pub fn notch(&self) -> Option<u32> {
+        self.notch_freq
+    }
+
+    // manual notch on the audio [Hz]; None is off
+    pub fn set_notch(&mut self, freq: Option<u32>) {
+        self.notch_freq = freq.map(|f| f.clamp(Self::MIN_NOTCH, Self::MAX_NOTCH));
+        self.notch = self
+            .notch_freq
+            .map(|f| Notch::new(f, NOTCH_WIDTH, DS_RATE as u32));
+    }
+
+    pub fn auto_notch(&self) -> bool {
+        self.auto_notch.is_some()
+    }
+
+    pub fn set_auto_notch(&mut self, on: bool) {
+        if on != self.auto_notch() {
+            self.auto_notch = on.then(|| AutoNotch::new(ANF_DELAY, ANF_STEP));
+        }
+    }
+
+    // tune Shifter for the current method; call after changing a demodulator setting
+    pub fn configure(&mut self) {
+        let mode = self.demods.method().mode();
+        let d = self.demods.current();
+        // wideband methods take the input as is
+        let Some(bandwidth) = d.channel_bandwidth().or(mode.bandwidth) else {
+            return;
+        };
+        if self.shifter.bandwidth() != bandwidth {
+            self.shifter.set_bandwidth(bandwidth);
+        }
+        self.shifter.set_freq(-(self.freq + d.center_offset()));
+    }
+
+    // audio of a block into out at DS_RATE; L / R in re / im, the same for mono
+    pub fn process(
+        &mut self,
+        input: &[DSPComplex; Shifter::INPUT_SIZE],
+        out: &mut [DSPComplex; Shifter::OUTPUT_SIZE],
+    ) {
+        let mode = self.demods.method().mode();
+        let d = self.demods.current();
+        if mode.bandwidth.is_some() {
+            self.shifter.apply(input, out);
+            self.squelch.measure(out);
+            d.demod(out);
+        } else {
+            // wideband method takes the whole input as its channel
+            self.buf.copy_from_slice(input);
+            self.squelch.measure(&self.buf);
+            d.demod(&mut self.buf);
+            out.copy_from_slice(&self.buf[..Shifter::OUTPUT_SIZE]);
+        }
+
+        let quieting = d.quieting();
+        if let Some(q) = quieting {
+            self.squelch.measure_quieting(q);
+        }
+
+        let stereo = d.stereo();
+        if !stereo {
+            self.nr.process(out);
+            if let Some(n) = &mut self.auto_notch {
+                for x in out.iter_mut() {
+                    x.re = n.push(x.re);
+                }
+            }
+            if let Some(n) = &mut self.notch {
+                for x in out.iter_mut() {
+                    x.re = n.push(x.re);
+                }
+            }
+        }
+        self.agc.process(out, stereo);
+        self.squelch.apply(out, quieting.is_some());
+        if !stereo {
+            for x in out.iter_mut() {
+                x.im = x.re;
+            }
+        }
+    }
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// sum of two audio, saturating
+pub fn mix(a: &mut [DSPComplex], b: &[DSPComplex]) {
+    for (x, y) in a.iter_mut().zip(b) {
+        x.re = DSPNum(x.re.0.saturating_add(y.re.0));
+        x.im = DSPNum(x.im.0.saturating_add(y.im.0));
+    }
+}
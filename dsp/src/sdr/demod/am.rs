@@ -0,0 +1,14 @@
+use crate::dsp::DSPComplex;
+
+use super::Demodulator;
+
+// envelope detector
+pub struct AmDemod;
+
+impl Demodulator for AmDemod {
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        for b in buf {
+            *b = b.fast_abs().into();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::dsp::{
+    fir::{design_lowpass, Fir, FirDecimator, FirInterpolator},
+    window::Window,
+    DSPComplex, DSPNum,
+};
+use crate::sdr::DS_RATE;
+
+use super::Demodulator;
+
+const RATIO: usize = 4;
+// intermediate rates: 48kHz -> 12kHz -> 3kHz
+const RATE_MID: u32 = (DS_RATE / RATIO) as u32;
+const RATE_LOW: u32 = RATE_MID / RATIO as u32;
+
+/*
+CW demodulator
+
+Shifter should be tuned to the carrier. The channel is decimated down to 3kHz, where the
+narrow filter is cheap, brought back to 12kHz, mixed up by the BFO pitch and real part is
+taken, so the carrier is heard as a tone of the pitch.
+*/
+pub struct CwDemod {
+    pitch: u32,
+    width: u32,
+
+    phase: i32,
+    omega: i32,
+
+    decimator_mid: FirDecimator<DSPComplex, 64, RATIO>,
+    decimator_low: FirDecimator<DSPComplex, 64, RATIO>,
+    filter: Fir<DSPComplex, 96>,
+    interpolator_low: FirInterpolator<DSPComplex, 64, RATIO>,
+    interpolator_mid: FirInterpolator<DSPNum, 64, RATIO>,
+}
+
+impl CwDemod {
+    pub const MIN_PITCH: u32 = 400;
+    pub const MAX_PITCH: u32 = 1_000;
+    pub const DEFAULT_PITCH: u32 = 700;
+    pub const WIDTHS: [u32; 2] = [250, 500];
+
+    pub fn new() -> Self {
+        let mut s = Self {
+            pitch: 0,
+            width: 0,
+            phase: 0,
+            omega: 0,
+            decimator_mid: FirDecimator::lowpass(
+                RATE_MID / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+            decimator_low: FirDecimator::lowpass(RATE_LOW / 2, RATE_MID, Window::BlackmanHarris),
+            filter: Fir::new(&[DSPNum(0); 96]),
+            interpolator_low: FirInterpolator::lowpass(1_000, RATE_MID, Window::BlackmanHarris),
+            interpolator_mid: FirInterpolator::lowpass(
+                RATE_MID / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+        };
+        s.set_pitch(Self::DEFAULT_PITCH);
+        s.set_width(Self::WIDTHS[1]);
+        s
+    }
+
+    pub fn pitch(&self) -> u32 {
+        self.pitch
+    }
+
+    // BFO pitch [Hz]
+    pub fn set_pitch(&mut self, pitch: u32) {
+        self.pitch = pitch.clamp(Self::MIN_PITCH, Self::MAX_PITCH);
+        // theta = 1<<18 represents 2pi
+        self.omega = ((self.pitch << 18) / RATE_MID) as i32;
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    // audio bandwidth [Hz]
+    pub fn set_width(&mut self, width: u32) {
+        self.width = width;
+        let mut h = [DSPNum(0); 96];
+        design_lowpass(&mut h, width / 2, RATE_LOW, Window::BlackmanHarris);
+        self.filter.set_taps(&h);
+    }
+}
+
+impl Demodulator for CwDemod {
+    // buf: output of Shifter at DS_RATE; audio is written back to re
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        let mut up = [DSPComplex::zero(); RATIO];
+        let mut out = [DSPNum(0); RATIO];
+        for i in 0..buf.len() {
+            let Some(x) = self.decimator_mid.push(buf[i]) else {
+                continue;
+            };
+            let Some(x) = self.decimator_low.push(x) else {
+                continue;
+            };
+            let x = self.filter.push(x).unwrap();
+
+            self.interpolator_low.push(x, &mut up);
+            // index of the latest 12kHz sample
+            let j = i / RATIO;
+            for (q, u) in up.iter().enumerate() {
+                let y = u * DSPComplex::expi(self.phase);
+                self.phase = (self.phase + self.omega) & 0x3ffff;
+
+                self.interpolator_mid.push(y.re, &mut out);
+                // inputs up to i are already consumed
+                let base = (j + 1 - RATIO + q) * RATIO;
+                for (r, o) in out.iter().enumerate() {
+                    buf[base + r] = (*o).into();
+                }
+            }
+        }
+    }
+}
+
+impl Default for CwDemod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,59 @@
+use crate::dsp::DSPComplex;
+
+use super::Demodulator;
+
+/*
+wideband FM discriminator
+
+takes the raw input at SAMPLE_RATE; output is the phase difference of successive samples,
+1 << 16 being one turn per sample. phase is taken by CORDIC of `precision` iterations.
+*/
+pub struct FmDemod {
+    precision: usize,
+    last: i32,
+}
+
+impl FmDemod {
+    pub const MIN_PRECISION: usize = 1;
+    pub const MAX_PRECISION: usize = 16;
+    pub const DEFAULT_PRECISION: usize = 12;
+
+    pub fn new() -> Self {
+        Self::with_precision(Self::DEFAULT_PRECISION)
+    }
+
+    pub fn with_precision(precision: usize) -> Self {
+        let mut s = Self {
+            precision: 0,
+            last: 0,
+        };
+        s.set_precision(precision);
+        s
+    }
+
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+
+    // number of CORDIC iterations; each one adds about a bit of resolution and costs time
+    pub fn set_precision(&mut self, precision: usize) {
+        self.precision = precision.clamp(Self::MIN_PRECISION, Self::MAX_PRECISION);
+    }
+}
+
+impl Default for FmDemod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Demodulator for FmDemod {
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        for b in buf {
+            let p = b.phase_cordic(self.precision) >> 2; // 18 -> 16
+            let diff = p.wrapping_sub(self.last) as i16;
+            self.last = p;
+            *b = DSPComplex::from_i16(diff, 0);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+mod am;
+mod cw;
+mod fm;
+mod nfm;
+mod sam;
+mod ssb;
+mod stereo;
+mod wfm;
+pub use am::AmDemod;
+pub use cw::CwDemod;
+pub use fm::FmDemod;
+pub use nfm::NfmDemod;
+pub use sam::{SamDemod, SamSideband};
+pub use ssb::{Sideband, SsbDemod};
+pub use stereo::{Deemphasis, StereoDecoder};
+pub use wfm::WfmDemod;
+
+use crate::dsp::DSPComplex;
+use crate::sdr::{agc::AgcPreset, rds::RdsData, shift::Shifter};
+
+/*
+common interface of demodulators
+
+narrowband ones take the Shifter output at DS_RATE and write audio back to re.
+wideband ones (Mode::bandwidth is None) take the raw input at SAMPLE_RATE instead,
+and write audio at DS_RATE to the head of buf.
+*/
+pub trait Demodulator {
+    fn demod(&mut self, buf: &mut [DSPComplex]);
+
+    // Shifter is tuned this much off the carrier [Hz]
+    fn center_offset(&self) -> i32 {
+        0
+    }
+
+    // two-sided channel filter of Shifter, if it differs from Mode::bandwidth [Hz]
+    fn channel_bandwidth(&self) -> Option<u32> {
+        None
+    }
+
+    // right channel is written to im
+    fn stereo(&self) -> bool {
+        false
+    }
+
+    // carrier or pilot lock, if the method has one
+    fn locked(&self) -> bool {
+        false
+    }
+
+    // for noise squelch; see NfmDemod
+    fn quieting(&mut self) -> Option<i32> {
+        None
+    }
+
+    // RDS data, if changed since the last call
+    fn rds(&mut self) -> Option<RdsData> {
+        None
+    }
+
+    // forget the station, e.g. on tuning
+    fn reset(&mut self) {}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum DemodMethod {
+    AM,
+    FM,
+    USB,
+    LSB,
+    CW,
+    SAM,
+    NFM,
+}
+
+pub struct Mode {
+    pub method: DemodMethod,
+    // shown on display
+    pub name: &'static [u8; 3],
+    // two-sided channel filter of Shifter [Hz]; None for wideband, which bypasses it
+    pub bandwidth: Option<u32>,
+    // default AGC preset
+    pub agc: AgcPreset,
+}
+
+// in the order of DemodMethod
+pub const MODES: [Mode; 7] = [
+    Mode {
+        method: DemodMethod::AM,
+        name: b"AM ",
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Slow,
+    },
+    Mode {
+        method: DemodMethod::FM,
+        name: b"FM ",
+        bandwidth: None,
+        agc: AgcPreset::Off,
+    },
+    Mode {
+        method: DemodMethod::USB,
+        name: b"USB",
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Slow,
+    },
+    Mode {
+        method: DemodMethod::LSB,
+        name: b"LSB",
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Slow,
+    },
+    Mode {
+        method: DemodMethod::CW,
+        name: b"CW ",
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Fast,
+    },
+    Mode {
+        method: DemodMethod::SAM,
+        name: b"SAM",
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Slow,
+    },
+    Mode {
+        method: DemodMethod::NFM,
+        name: b"NFM",
+        // follows the channel spacing; see NfmDemod::bandwidth
+        bandwidth: Some(Shifter::DEFAULT_BANDWIDTH),
+        agc: AgcPreset::Off,
+    },
+];
+
+const _: () = {
+    let mut i = 0;
+    while i < MODES.len() {
+        assert!(
+            MODES[i].method as usize == i,
+            "MODES must be in the order of DemodMethod"
+        );
+        i += 1;
+    }
+};
+
+impl DemodMethod {
+    pub const METHOD_COUNT: u8 = MODES.len() as u8;
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        MODES.get(value as usize).map(|m| m.method)
+    }
+
+    pub fn mode(self) -> &'static Mode {
+        &MODES[self as usize]
+    }
+
+    // `rot` steps forward in MODES, wrapping around
+    pub fn rotate(self, rot: i32) -> Self {
+        let n = MODES.len() as i32;
+        MODES[(self as i32 + rot).rem_euclid(n) as usize].method
+    }
+}
+
+// one demodulator per method, keeping their settings while not in use
+pub struct Demodulators {
+    method: DemodMethod,
+    pub am: AmDemod,
+    pub fm: WfmDemod,
+    pub ssb: SsbDemod,
+    pub cw: CwDemod,
+    pub sam: SamDemod,
+    pub nfm: NfmDemod,
+}
+
+impl Demodulators {
+    pub fn new() -> Self {
+        Self {
+            method: DemodMethod::AM,
+            am: AmDemod,
+            fm: WfmDemod::new(),
+            ssb: SsbDemod::new(Sideband::Upper),
+            cw: CwDemod::new(),
+            sam: SamDemod::new(SamSideband::Double),
+            nfm: NfmDemod::new(NfmDemod::CHANNELS[0]),
+        }
+    }
+
+    pub fn method(&self) -> DemodMethod {
+        self.method
+    }
+
+    pub fn set_method(&mut self, method: DemodMethod) {
+        self.method = method;
+        match method {
+            DemodMethod::USB => self.ssb.set_sideband(Sideband::Upper),
+            DemodMethod::LSB => self.ssb.set_sideband(Sideband::Lower),
+            _ => {}
+        }
+    }
+
+    pub fn current(&mut self) -> &mut dyn Demodulator {
+        match self.method {
+            DemodMethod::AM => &mut self.am,
+            DemodMethod::FM => &mut self.fm,
+            DemodMethod::USB | DemodMethod::LSB => &mut self.ssb,
+            DemodMethod::CW => &mut self.cw,
+            DemodMethod::SAM => &mut self.sam,
+            DemodMethod::NFM => &mut self.nfm,
+        }
+    }
+}
+
+impl Default for Demodulators {
+    fn default() -> Self {
+        Self::new()
+    }
+}
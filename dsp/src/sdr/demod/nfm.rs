@@ -0,0 +1,129 @@
+use crate::dsp::{db10, fir::Fir, window::Window, DSPComplex, DSPNum};
+use crate::sdr::DS_RATE;
+
+use super::Demodulator;
+
+/*
+narrowband FM demodulator
+
+works on the Shifter output at DS_RATE. phase difference of successive samples is taken
+from x[n] * conj(x[n-1]) with a rational atan approximation, which is good enough within
++-pi/4 (about 6kHz deviation). peak deviation of the channel is scaled to half of full scale.
+*/
+pub struct NfmDemod {
+    last: DSPComplex,
+    channel: u32,
+    // Q14 audio per Q13 radian, Q8
+    gain: i32,
+    audio: Fir<DSPNum, 32>,
+
+    // second difference of the discriminator output, for noise squelch
+    hist: [i32; 2],
+    noise: u64,
+    noise_count: u32,
+}
+
+impl NfmDemod {
+    // channel spacing [Hz]
+    pub const CHANNELS: [u32; 2] = [12_500, 25_000];
+    const AUDIO_CUTOFF: u32 = 4_000;
+    // discriminator noise power without signal, Q8 dB
+    const NOISE_FLOOR: i32 = 75 << 8;
+
+    pub fn new(channel: u32) -> Self {
+        let mut s = Self {
+            last: DSPComplex::zero(),
+            channel: 0,
+            gain: 0,
+            audio: Fir::lowpass(Self::AUDIO_CUTOFF, DS_RATE as u32, Window::Hann),
+            hist: [0; 2],
+            noise: 0,
+            noise_count: 0,
+        };
+        s.set_channel(channel);
+        s
+    }
+
+    pub fn channel(&self) -> u32 {
+        self.channel
+    }
+
+    pub fn set_channel(&mut self, channel: u32) {
+        self.channel = channel;
+        // 0.5 at peak: 1 / (2pi * deviation / rate)
+        self.gain = ((DS_RATE as i64 * 256 * 10_000) / (62_832 * self.deviation() as i64)) as i32;
+    }
+
+    // peak deviation of the channel [Hz]
+    pub fn deviation(&self) -> u32 {
+        if self.channel >= 25_000 {
+            5_000
+        } else {
+            2_500
+        }
+    }
+
+    // two-sided bandwidth for the channel filter of Shifter [Hz], by Carson's rule
+    pub fn bandwidth(&self) -> u32 {
+        2 * (self.deviation() + 3_000)
+    }
+}
+
+impl Demodulator for NfmDemod {
+    fn channel_bandwidth(&self) -> Option<u32> {
+        Some(self.bandwidth())
+    }
+
+    // how much the discriminator noise is suppressed by a carrier, since the last call [Q8 dB]
+    // 0 without signal; grows with carrier to noise ratio
+    fn quieting(&mut self) -> Option<i32> {
+        if self.noise_count == 0 {
+            return Some(0);
+        }
+        let n = self.noise / self.noise_count as u64;
+        self.noise = 0;
+        self.noise_count = 0;
+        Some(Self::NOISE_FLOOR - db10(n.max(1)))
+    }
+
+    // audio is written back to re
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        for x in buf.iter_mut() {
+            let (xr, xi) = (x.re.0 as i64, x.im.0 as i64);
+            let (lr, li) = (self.last.re.0 as i64, self.last.im.0 as i64);
+            self.last = *x;
+
+            // x * conj(last)
+            let mut a = xr * lr + xi * li;
+            let mut b = xi * lr - xr * li;
+            // normalize to 15bit; avoids overflow below and keeps precision of weak signals
+            let bits = 64 - (a.unsigned_abs() | b.unsigned_abs()).leading_zeros();
+            if bits > 15 {
+                a >>= bits - 15;
+                b >>= bits - 15;
+            } else {
+                a <<= 15 - bits;
+                b <<= 15 - bits;
+            }
+
+            // atan(b / a) ~= a * b / (a^2 + 0.28125 b^2), Q13 radian
+            let den = a * a + ((9 * b * b) >> 5);
+            let theta = if den == 0 {
+                // no input at all; this is not a noise to measure
+                0
+            } else {
+                let theta = (((a * b) << 13) / den) as i32;
+                // noise is mostly above the audio band
+                let d = theta - 2 * self.hist[0] + self.hist[1];
+                self.hist = [theta, self.hist[0]];
+                self.noise += (d as i64 * d as i64) as u64;
+                self.noise_count += 1;
+                theta
+            };
+
+            let v = (theta * self.gain) >> 8;
+            let v = DSPNum(v.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+            *x = self.audio.push(v).unwrap().into();
+        }
+    }
+}
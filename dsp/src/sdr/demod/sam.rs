@@ -0,0 +1,126 @@
+use crate::dsp::{
+    fir::{design_hilbert, Fir, FirDecimator, FirInterpolator},
+    pll::Pll,
+    window::Window,
+    DSPComplex, DSPNum,
+};
+use crate::sdr::DS_RATE;
+
+use super::Demodulator;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum SamSideband {
+    Double,
+    Upper,
+    Lower,
+}
+
+impl SamSideband {
+    pub const ALL: [SamSideband; 3] = [SamSideband::Double, SamSideband::Upper, SamSideband::Lower];
+}
+
+const RATIO: usize = 4;
+const RATE: u32 = (DS_RATE / RATIO) as u32;
+const HILBERT_TAPS: usize = 65;
+const HILBERT_DELAY: usize = HILBERT_TAPS / 2;
+
+/*
+synchronous AM demodulator
+
+Shifter should be tuned to the carrier. Here it is decimated to 12kHz, and the PLL rotates
+the carrier onto the real axis. Then I is the audio of both sidebands, and one sideband can
+be cancelled with the Hilbert transformed Q (USB: I - H(Q), LSB: I + H(Q)).
+*/
+pub struct SamDemod {
+    sideband: SamSideband,
+    pll: Pll,
+    // averaged carrier level << 8
+    carrier: i32,
+
+    decimator: FirDecimator<DSPComplex, 64, RATIO>,
+    hilbert: Fir<DSPNum, HILBERT_TAPS>,
+    // delays I to match the Hilbert transformer
+    delay: [DSPNum; HILBERT_DELAY],
+    delay_pos: usize,
+    interpolator: FirInterpolator<DSPNum, 64, RATIO>,
+}
+
+impl SamDemod {
+    const PLL_BANDWIDTH: u32 = 30;
+    const PLL_LIMIT: u32 = 500;
+
+    pub fn new(sideband: SamSideband) -> Self {
+        let mut h = [DSPNum(0); HILBERT_TAPS];
+        design_hilbert(&mut h, Window::BlackmanHarris);
+        Self {
+            sideband,
+            pll: Pll::new(Self::PLL_BANDWIDTH, Self::PLL_LIMIT, RATE),
+            carrier: 0,
+            decimator: FirDecimator::lowpass(RATE / 2, DS_RATE as u32, Window::BlackmanHarris),
+            hilbert: Fir::new(&h),
+            delay: [DSPNum(0); HILBERT_DELAY],
+            delay_pos: 0,
+            interpolator: FirInterpolator::lowpass(
+                RATE / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+        }
+    }
+
+    pub fn sideband(&self) -> SamSideband {
+        self.sideband
+    }
+
+    pub fn set_sideband(&mut self, sideband: SamSideband) {
+        self.sideband = sideband;
+    }
+
+    // carrier offset from the tuned frequency [Hz]
+    pub fn carrier_offset(&self) -> i32 {
+        self.pll.freq()
+    }
+}
+
+impl Demodulator for SamDemod {
+    fn locked(&self) -> bool {
+        self.pll.locked()
+    }
+
+    // buf: output of Shifter at DS_RATE; audio is written back to re
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        let mut out = [DSPNum(0); RATIO];
+        for i in 0..buf.len() {
+            let Some(x) = self.decimator.push(buf[i]) else {
+                continue;
+            };
+            let y = self.pll.track(x);
+
+            // remove the carrier
+            self.carrier += (((y.re.0 as i32) << 8) - self.carrier) >> 8;
+            let re = (y.re.0 as i32 - (self.carrier >> 8)).clamp(i16::MIN as i32, i16::MAX as i32);
+
+            let q = self.hilbert.push(y.im).unwrap().0 as i32;
+            let re =
+                core::mem::replace(&mut self.delay[self.delay_pos], DSPNum(re as i16)).0 as i32;
+            self.delay_pos = if self.delay_pos + 1 == HILBERT_DELAY {
+                0
+            } else {
+                self.delay_pos + 1
+            };
+
+            let a = match self.sideband {
+                SamSideband::Double => re,
+                SamSideband::Upper => re - q,
+                SamSideband::Lower => re + q,
+            };
+            let a = DSPNum(a.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+
+            self.interpolator.push(a, &mut out);
+            // inputs up to i are already consumed
+            for (j, o) in out.iter().enumerate() {
+                buf[i + 1 - RATIO + j] = (*o).into();
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::dsp::{
+    fir::{design_lowpass, Fir, FirDecimator, FirInterpolator},
+    window::Window,
+    DSPComplex, DSPNum,
+};
+use crate::sdr::DS_RATE;
+
+use super::Demodulator;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Sideband {
+    Upper,
+    Lower,
+}
+
+const RATIO: usize = 4;
+const RATE: u32 = (DS_RATE / RATIO) as u32;
+
+/*
+Weaver method SSB demodulator
+
+Shifter should be tuned to the center of the sideband (see center_offset()), so that
+the sideband sits symmetric around DC. Here it is decimated to 12kHz, lowpassed to
+half of the audio bandwidth, shifted back by the center frequency and real part is taken.
+*/
+pub struct SsbDemod {
+    sideband: Sideband,
+    high_cut: u32,
+
+    phase: i32,
+    omega: i32,
+
+    decimator: FirDecimator<DSPComplex, 64, RATIO>,
+    filter: Fir<DSPComplex, 64>,
+    interpolator: FirInterpolator<DSPNum, 64, RATIO>,
+}
+
+impl SsbDemod {
+    pub const LOW_CUT: u32 = 300;
+    pub const MIN_BANDWIDTH: u32 = 1_800;
+    pub const MAX_BANDWIDTH: u32 = 3_000;
+    pub const DEFAULT_BANDWIDTH: u32 = 2_400;
+
+    pub fn new(sideband: Sideband) -> Self {
+        let mut s = Self {
+            sideband,
+            high_cut: 0,
+            phase: 0,
+            omega: 0,
+            decimator: FirDecimator::lowpass(RATE / 2, DS_RATE as u32, Window::BlackmanHarris),
+            filter: Fir::new(&[DSPNum(0); 64]),
+            interpolator: FirInterpolator::lowpass(
+                RATE / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+        };
+        s.set_bandwidth(Self::DEFAULT_BANDWIDTH);
+        s
+    }
+
+    pub fn sideband(&self) -> Sideband {
+        self.sideband
+    }
+
+    pub fn set_sideband(&mut self, sideband: Sideband) {
+        self.sideband = sideband;
+        self.update_omega();
+    }
+
+    pub fn bandwidth(&self) -> u32 {
+        self.high_cut
+    }
+
+    // audio passband is LOW_CUT..bandwidth [Hz]
+    pub fn set_bandwidth(&mut self, bandwidth: u32) {
+        self.high_cut = bandwidth.clamp(Self::MIN_BANDWIDTH, Self::MAX_BANDWIDTH);
+        let mut h = [DSPNum(0); 64];
+        design_lowpass(
+            &mut h,
+            (self.high_cut - Self::LOW_CUT) / 2,
+            RATE,
+            Window::Hann,
+        );
+        self.filter.set_taps(&h);
+        self.update_omega();
+    }
+
+    fn update_omega(&mut self) {
+        // theta = 1<<18 represents 2pi
+        self.omega = (self.center_offset() << 18) / RATE as i32;
+    }
+}
+
+impl Demodulator for SsbDemod {
+    // offset of the sideband center from the carrier [Hz]
+    fn center_offset(&self) -> i32 {
+        let c = ((Self::LOW_CUT + self.high_cut) / 2) as i32;
+        match self.sideband {
+            Sideband::Upper => c,
+            Sideband::Lower => -c,
+        }
+    }
+
+    // buf: output of Shifter at DS_RATE; audio is written back to re
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        let mut out = [DSPNum(0); RATIO];
+        for i in 0..buf.len() {
+            let Some(x) = self.decimator.push(buf[i]) else {
+                continue;
+            };
+            let x = self.filter.push(x).unwrap() * DSPComplex::expi(self.phase);
+            self.phase = (self.phase + self.omega) & 0x3ffff;
+
+            self.interpolator.push(x.re, &mut out);
+            // inputs up to i are already consumed
+            for (j, o) in out.iter().enumerate() {
+                buf[i + 1 - RATIO + j] = (*o).into();
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::dsp::{fir::FirDecimator, pll::Pll, window::Window, DSPComplex, DSPNum};
+use crate::sdr::{DS_RATE, DS_RATIO};
+use crate::SAMPLE_RATE;
+
+// pilot is 19 / 192 of SAMPLE_RATE; mixer table repeats every 192 samples
+const PILOT_PERIOD: usize = 192;
+const PILOT_CYCLES: usize = 19;
+const PILOT_RATIO: usize = 8;
+const PILOT_RATE: u32 = (DS_RATE / PILOT_RATIO) as u32;
+// L+R / L-R filters: flat to 19kHz, -75dB from 33kHz, which would fold into the audio
+const AUDIO_TAPS: usize = 80;
+const AUDIO_CUTOFF: u32 = 24_000;
+
+/*
+FM broadcast stereo decoder
+
+takes the discriminator output (MPX) at SAMPLE_RATE in re, and writes L / R at DS_RATE
+into re / im. the 19kHz pilot is mixed down to DC, narrowed to +-1kHz at 6kHz and tracked
+by a PLL; the 38kHz subcarrier is regenerated at twice the pilot phase.
+falls back to mono while the pilot is not locked.
+*/
+pub struct StereoDecoder {
+    // exp(-i * pilot phase of the free running mixer)
+    rot: [DSPComplex; PILOT_PERIOD],
+    idx: usize,
+
+    pilot_decimator: FirDecimator<DSPComplex, 32, DS_RATIO>,
+    pilot_filter: FirDecimator<DSPComplex, 64, PILOT_RATIO>,
+    pll: Pll,
+    // exp(2i * pll phase)
+    sub_rot: DSPComplex,
+
+    // L+R and L-R audio
+    sum: FirDecimator<DSPNum, AUDIO_TAPS, DS_RATIO>,
+    diff: FirDecimator<DSPNum, AUDIO_TAPS, DS_RATIO>,
+
+    deemphasis: [Deemphasis; 2],
+}
+
+impl StereoDecoder {
+    // time constants in use [us]
+    pub const DEEMPHASIS: [u32; 2] = [50, 75];
+    pub const DEFAULT_DEEMPHASIS: u32 = 50;
+
+    pub fn new() -> Self {
+        let mut rot = [DSPComplex::zero(); PILOT_PERIOD];
+        for (i, x) in rot.iter_mut().enumerate() {
+            // theta = 1<<18 represents 2pi
+            let p = (i * PILOT_CYCLES % PILOT_PERIOD) as i32;
+            *x = DSPComplex::expi(-((p << 18) / PILOT_PERIOD as i32));
+        }
+        Self {
+            rot,
+            idx: 0,
+            pilot_decimator: FirDecimator::lowpass(
+                4_000,
+                SAMPLE_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+            pilot_filter: FirDecimator::lowpass(1_000, DS_RATE as u32, Window::BlackmanHarris),
+            pll: Pll::new(10, 50, PILOT_RATE),
+            sub_rot: DSPComplex::one(),
+            sum: FirDecimator::lowpass(AUDIO_CUTOFF, SAMPLE_RATE as u32, Window::BlackmanHarris),
+            diff: FirDecimator::lowpass(AUDIO_CUTOFF, SAMPLE_RATE as u32, Window::BlackmanHarris),
+            deemphasis: [Deemphasis::new(Self::DEFAULT_DEEMPHASIS); 2],
+        }
+    }
+
+    // de-emphasis time constant [us]
+    pub fn set_deemphasis(&mut self, tau: u32) {
+        self.deemphasis = [Deemphasis::new(tau); 2];
+    }
+
+    pub fn is_stereo(&self) -> bool {
+        self.pll.locked()
+    }
+
+    // returns number of written outputs
+    pub fn process(&mut self, mpx: &[DSPComplex], output: &mut [DSPComplex]) -> usize {
+        let mut k = 0;
+        for x in mpx {
+            let m = x.re;
+            let r = self.rot[self.idx];
+            if let Some(p) = self.pilot_decimator.push(r * m) {
+                if let Some(p) = self.pilot_filter.push(p) {
+                    self.pll.track(p);
+                    self.sub_rot = DSPComplex::expi(2 * self.pll.phase());
+                }
+            }
+
+            // pilot is cos(phi), then L-R is carried on -sin(2 phi)
+            let s = (self.rot[self.idx * 2 % PILOT_PERIOD].conj() * self.sub_rot).im;
+            let d = -(m * s);
+
+            self.idx = if self.idx + 1 == PILOT_PERIOD {
+                0
+            } else {
+                self.idx + 1
+            };
+
+            let (Some(a), Some(b)) = (self.sum.push(m), self.diff.push(d)) else {
+                continue;
+            };
+            let (a, b) = (a.0 as i32, b.0 as i32 * 2);
+            let (l, r) = if self.is_stereo() {
+                (a + b, a - b)
+            } else {
+                (a, a)
+            };
+            output[k] = DSPComplex {
+                re: self.deemphasis[0].push(l),
+                im: self.deemphasis[1].push(r),
+            };
+            k += 1;
+        }
+        k
+    }
+}
+
+impl Default for StereoDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// first order de-emphasis lowpass at DS_RATE
+#[derive(Copy, Clone)]
+pub struct Deemphasis {
+    // Q14
+    alpha: i32,
+    // << 8
+    y: i32,
+}
+
+impl Deemphasis {
+    // tau: time constant [us]
+    pub fn new(tau: u32) -> Self {
+        // alpha = 1 - exp(-1 / (rate * tau)), by series in Q16
+        let x = (1_000_000i64 << 16) / (DS_RATE as i64 * tau.max(1) as i64);
+        let mut term = 1i64 << 16;
+        let mut e = term;
+        for k in 1..10 {
+            term = -term * x / (k << 16);
+            e += term;
+        }
+        Self {
+            alpha: (((1 << 16) - e) >> 2) as i32,
+            y: 0,
+        }
+    }
+
+    // input is i32 to take L+R and L-R sums without overflow
+    #[inline]
+    pub fn push(&mut self, x: i32) -> DSPNum {
+        let d = ((x << 8) - self.y) as i64;
+        self.y += ((self.alpha as i64 * d) >> DSPNum::FIXED_POINT) as i32;
+        DSPNum((self.y >> 8).clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+    }
+}
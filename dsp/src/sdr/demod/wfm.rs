@@ -0,0 +1,73 @@
+use crate::dsp::DSPComplex;
+use crate::sdr::{
+    rds::{RdsData, RdsDecoder},
+    shift::Shifter,
+};
+
+use super::{Demodulator, FmDemod, StereoDecoder};
+
+/*
+broadcast FM receiver
+
+discriminator, stereo decoder and RDS on a block of the raw input (Shifter::INPUT_SIZE
+samples at SAMPLE_RATE). L / R are written to re / im of the head of buf, at DS_RATE.
+*/
+pub struct WfmDemod {
+    fm: FmDemod,
+    stereo: StereoDecoder,
+    rds: RdsDecoder,
+    // RDS data is changed since the last rds()
+    updated: bool,
+    out: [DSPComplex; Shifter::OUTPUT_SIZE],
+}
+
+impl WfmDemod {
+    pub fn new() -> Self {
+        Self {
+            fm: FmDemod::new(),
+            stereo: StereoDecoder::new(),
+            rds: RdsDecoder::new(),
+            updated: false,
+            out: [DSPComplex::zero(); Shifter::OUTPUT_SIZE],
+        }
+    }
+
+    // de-emphasis time constant [us]
+    pub fn set_deemphasis(&mut self, tau: u32) {
+        self.stereo.set_deemphasis(tau);
+    }
+}
+
+impl Default for WfmDemod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Demodulator for WfmDemod {
+    fn demod(&mut self, buf: &mut [DSPComplex]) {
+        self.fm.demod(buf);
+        self.updated |= self.rds.process(buf);
+        let n = self.stereo.process(buf, &mut self.out);
+        buf[..n].copy_from_slice(&self.out[..n]);
+    }
+
+    fn stereo(&self) -> bool {
+        true
+    }
+
+    fn locked(&self) -> bool {
+        self.stereo.is_stereo()
+    }
+
+    fn rds(&mut self) -> Option<RdsData> {
+        let updated = core::mem::take(&mut self.updated);
+        updated.then(|| *self.rds.data())
+    }
+
+    // RDS belongs to the station
+    fn reset(&mut self) {
+        self.rds.reset();
+        self.updated = true;
+    }
+}
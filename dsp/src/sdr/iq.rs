@@ -0,0 +1,128 @@
+use crate::dsp::DSPComplex;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum IqMode {
+    // raw input
+    Off,
+    // DC removed, imbalance estimated from the signal
+    Track,
+    // DC removed, imbalance corrected by the coefficients as set
+    Hold,
+}
+
+impl IqMode {
+    pub const ALL: [IqMode; 3] = [IqMode::Off, IqMode::Track, IqMode::Hold];
+}
+
+// DC follows over 1 << DC_SHIFT samples, about 20ms
+const DC_SHIFT: u32 = 12;
+// imbalance follows over 1 << STEP_SHIFT blocks
+const STEP_SHIFT: u32 = 8;
+const ONE: i32 = 1 << 24;
+
+/*
+IQ DC offset and imbalance correction, on the raw input
+
+the quadrature LO is not exactly 90 degrees apart nor of the same amplitude, which makes
+an image of every signal mirrored around DC. I is taken as the reference, and Q is
+corrected as Q' = gain * (Q - phase * I). for any signal without image, I and Q' are
+uncorrelated and of the same power; in Track, phase and gain are adjusted every block
+toward that.
+
+mixing on the 3rd harmonic of the LO, Q is 270 degrees behind I, and the spectrum comes
+inverted; with swap, I and Q are exchanged first to turn it back.
+*/
+pub struct IqCorrection {
+    mode: IqMode,
+    swap: bool,
+    // << 12
+    dc: [i32; 2],
+    // Q24
+    phase: i32,
+    gain: i32,
+}
+
+impl IqCorrection {
+    pub fn new() -> Self {
+        Self {
+            mode: IqMode::Track,
+            swap: false,
+            dc: [0; 2],
+            phase: 0,
+            gain: ONE,
+        }
+    }
+
+    pub fn mode(&self) -> IqMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: IqMode) {
+        self.mode = mode;
+    }
+
+    pub fn swap(&self) -> bool {
+        self.swap
+    }
+
+    pub fn set_swap(&mut self, swap: bool) {
+        self.swap = swap;
+    }
+
+    // (phase, gain) in Q24; to be stored and restored
+    pub fn coefficients(&self) -> (i32, i32) {
+        (self.phase, self.gain)
+    }
+
+    pub fn set_coefficients(&mut self, phase: i32, gain: i32) {
+        self.phase = phase.clamp(-ONE / 4, ONE / 4);
+        self.gain = gain.clamp(ONE / 2, ONE * 3 / 2);
+    }
+
+    pub fn process(&mut self, buf: &mut [DSPComplex]) {
+        if self.mode == IqMode::Off {
+            if self.swap {
+                for x in buf.iter_mut() {
+                    *x = DSPComplex::from_i16(x.im.0, x.re.0);
+                }
+            }
+            return;
+        }
+        // Q14 for the multiplications
+        let phase = self.phase >> 10;
+        let gain = self.gain >> 10;
+        let (mut ii, mut iq, mut qq) = (0i64, 0i64, 0i64);
+        let sat = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32);
+        for x in buf.iter_mut() {
+            let (re, im) = if self.swap {
+                (x.im.0 as i32, x.re.0 as i32)
+            } else {
+                (x.re.0 as i32, x.im.0 as i32)
+            };
+            self.dc[0] += ((re << 12) - self.dc[0]) >> DC_SHIFT;
+            self.dc[1] += ((im << 12) - self.dc[1]) >> DC_SHIFT;
+            let i = sat(re - (self.dc[0] >> 12));
+            let q = sat(im - (self.dc[1] >> 12));
+            let q = sat(((q - ((phase * i) >> 14)) * gain) >> 14);
+
+            ii += (i * i) as i64;
+            iq += (i * q) as i64;
+            qq += (q * q) as i64;
+            *x = DSPComplex::from_i16(i as i16, q as i16);
+        }
+
+        if self.mode != IqMode::Track || ii == 0 || qq == 0 {
+            return;
+        }
+        // correlation left, and relative power error of Q
+        let dp = ((iq << 24) / ii) >> STEP_SHIFT;
+        let dg = ((self.gain as i64 * (ii - qq)) / (2 * qq)) >> STEP_SHIFT;
+        self.set_coefficients(self.phase + dp as i32, self.gain + dg as i32);
+    }
+}
+
+impl Default for IqCorrection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,5 +3,14 @@ use crate::SAMPLE_RATE;
 pub const DS_RATIO: usize = 4;
 pub const DS_RATE: usize = SAMPLE_RATE / DS_RATIO;
 
+pub mod agc;
+pub mod blanker;
+pub mod channel;
 pub mod demod;
+pub mod iq;
+pub mod nr;
+pub mod rds;
+pub mod refcal;
 pub mod shift;
+pub mod smeter;
+pub mod squelch;
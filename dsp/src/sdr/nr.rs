@@ -0,0 +1,112 @@
+use crate::dsp::{
+    fir::{FirDecimator, FirInterpolator},
+    lms::Lms,
+    window::Window,
+    DSPComplex, DSPNum,
+};
+
+use super::DS_RATE;
+
+const RATIO: usize = 4;
+const RATE: u32 = (DS_RATE / RATIO) as u32;
+const TAPS: usize = 32;
+// samples at RATE; white noise is uncorrelated beyond this
+const DELAY: usize = 2;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum NrLevel {
+    Off,
+    Low,
+    Mid,
+    High,
+}
+
+impl NrLevel {
+    pub const ALL: [NrLevel; 4] = [NrLevel::Off, NrLevel::Low, NrLevel::Mid, NrLevel::High];
+
+    // step of Lms [Q16], and ratio of the prediction in the output [Q14]
+    fn params(&self) -> Option<(u32, i32)> {
+        match self {
+            NrLevel::Off => None,
+            // larger step follows voice faster, but leaves more noise
+            NrLevel::Low => Some((1 << 10, 1 << 13)),
+            NrLevel::Mid => Some((1 << 9, 3 << 12)),
+            NrLevel::High => Some((1 << 8, 1 << 14)),
+        }
+    }
+}
+
+/*
+audio noise reduction for voice
+
+decimated to 12kHz, where Lms predicts the audio from its past; voice and tones are
+predictable and noise is not, so the prediction is mostly the former. output is the
+prediction mixed with the input by the level, interpolated back to DS_RATE.
+a block of 48 samples costs about 2300 MACs.
+*/
+pub struct NoiseReduction {
+    level: NrLevel,
+    step: u32,
+    mix: i32,
+
+    decimator: FirDecimator<DSPNum, 64, RATIO>,
+    lms: Lms<TAPS>,
+    interpolator: FirInterpolator<DSPNum, 64, RATIO>,
+}
+
+impl NoiseReduction {
+    pub fn new() -> Self {
+        Self {
+            level: NrLevel::Off,
+            step: 0,
+            mix: 0,
+            decimator: FirDecimator::lowpass(RATE / 2, DS_RATE as u32, Window::BlackmanHarris),
+            lms: Lms::new(DELAY, 0, 0),
+            interpolator: FirInterpolator::lowpass(
+                RATE / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+        }
+    }
+
+    pub fn level(&self) -> NrLevel {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: NrLevel) {
+        self.level = level;
+        if let Some((step, mix)) = level.params() {
+            self.step = step;
+            self.mix = mix;
+            self.lms.set_step(step);
+        }
+    }
+
+    // audio in re at DS_RATE, processed in place
+    pub fn process(&mut self, buf: &mut [DSPComplex]) {
+        if self.level == NrLevel::Off {
+            return;
+        }
+        let mut out = [DSPNum(0); RATIO];
+        for i in 0..buf.len() {
+            let Some(x) = self.decimator.push(buf[i].re) else {
+                continue;
+            };
+            let y = self.lms.push(x);
+            let y = x.0 as i32 + (((y.0 as i32 - x.0 as i32) * self.mix) >> 14);
+
+            self.interpolator.push(DSPNum(y as i16), &mut out);
+            // inputs up to i are already consumed
+            for (j, o) in out.iter().enumerate() {
+                buf[i + 1 - RATIO + j].re = *o;
+            }
+        }
+    }
+}
+
+impl Default for NoiseReduction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
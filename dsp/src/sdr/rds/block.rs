@@ -0,0 +1,170 @@
+// RDS block synchronization and (26,16) shortened cyclic code
+
+// g(x) = x^10 + x^8 + x^7 + x^5 + x^4 + x^3 + 1
+const POLY: u32 = 0x5b9;
+const BLOCK_BITS: u32 = 26;
+const BLOCK_MASK: u32 = (1 << BLOCK_BITS) - 1;
+
+// offset words of A, B, C, D, and C'
+const OFFSETS: [u16; 5] = [0x0fc, 0x198, 0x168, 0x1b4, 0x350];
+const OFFSET_C2: usize = 4;
+
+// sync is dropped after this many uncorrectable blocks in a row
+const MAX_BAD_BLOCKS: u8 = 8;
+
+// remainder of w(x) / g(x)
+fn syndrome(w: u32) -> u16 {
+    let mut r = w;
+    for i in (10..BLOCK_BITS).rev() {
+        if r & (1 << i) != 0 {
+            r ^= POLY << (i - 10);
+        }
+    }
+    r as u16
+}
+
+// correct a burst error of up to 5 bits; returns corrected block
+fn correct(w: u32, offset: u16) -> Option<u32> {
+    let es = syndrome(w) ^ offset;
+    if es == 0 {
+        return Some(w);
+    }
+    // burst patterns start with 1
+    for p in (1..32u32).step_by(2) {
+        // syndrome of p << pos, computed by shifting in the code's LFSR
+        let mut s = p;
+        for pos in 0..BLOCK_BITS {
+            if p << pos > BLOCK_MASK {
+                break;
+            }
+            if s as u16 == es {
+                return Some(w ^ (p << pos));
+            }
+            s <<= 1;
+            if s & (1 << 10) != 0 {
+                s ^= POLY;
+            }
+        }
+    }
+    None
+}
+
+// one group; None for blocks which could not be received
+pub type Group = [Option<u16>; 4];
+
+pub struct BlockSync {
+    // last 26 bits
+    reg: u32,
+    bits: u32,
+
+    synced: bool,
+    // while searching: bit count and block index of the last valid block
+    last_match: Option<(u32, usize)>,
+    // while synced: index of next block, bits into it
+    next: usize,
+    pos: u32,
+    bad: u8,
+
+    group: Group,
+}
+
+impl BlockSync {
+    pub fn new() -> Self {
+        Self {
+            reg: 0,
+            bits: 0,
+            synced: false,
+            last_match: None,
+            next: 0,
+            pos: 0,
+            bad: 0,
+            group: [None; 4],
+        }
+    }
+
+    pub fn synced(&self) -> bool {
+        self.synced
+    }
+
+    // feed one bit; returns a group when its last block is received
+    pub fn push(&mut self, bit: bool) -> Option<Group> {
+        self.reg = ((self.reg << 1) | bit as u32) & BLOCK_MASK;
+        self.bits = self.bits.wrapping_add(1);
+
+        if !self.synced {
+            self.search();
+            return None;
+        }
+
+        self.pos += 1;
+        if self.pos < BLOCK_BITS {
+            return None;
+        }
+        self.pos = 0;
+
+        let idx = self.next;
+        let mut block = correct(self.reg, OFFSETS[idx]);
+        if idx == 2 && block.is_none() {
+            // B version group has C' instead
+            block = correct(self.reg, OFFSETS[OFFSET_C2]);
+        }
+
+        match block {
+            Some(_) => self.bad = 0,
+            None => {
+                self.bad += 1;
+                if self.bad >= MAX_BAD_BLOCKS {
+                    self.reset();
+                    return None;
+                }
+            }
+        }
+        self.group[idx] = block.map(|b| (b >> 10) as u16);
+
+        self.next = (idx + 1) % 4;
+        if self.next == 0 {
+            let g = self.group;
+            self.group = [None; 4];
+            return Some(g);
+        }
+        None
+    }
+
+    pub fn reset(&mut self) {
+        self.synced = false;
+        self.last_match = None;
+        self.bad = 0;
+        self.group = [None; 4];
+    }
+
+    // look for two valid blocks in a row, 26 bits apart
+    fn search(&mut self) {
+        let s = syndrome(self.reg);
+        let Some(idx) = OFFSETS.iter().position(|&o| o == s) else {
+            return;
+        };
+        let idx = if idx == OFFSET_C2 { 2 } else { idx };
+
+        if let Some((b, i)) = self.last_match {
+            if self.bits.wrapping_sub(b) == BLOCK_BITS && (i + 1) % 4 == idx {
+                self.synced = true;
+                self.next = (idx + 1) % 4;
+                self.pos = 0;
+                self.bad = 0;
+                self.group = [None; 4];
+                // keep what we have of the current group
+                if idx > 0 {
+                    self.group[idx] = Some((self.reg >> 10) as u16);
+                }
+                return;
+            }
+        }
+        self.last_match = Some((self.bits, idx));
+    }
+}
+
+impl Default for BlockSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,141 @@
+// RDS group parser
+
+use super::block::Group;
+
+// clock time of group 4A
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct ClockTime {
+    // modified julian day
+    pub mjd: u32,
+    // UTC
+    pub hour: u8,
+    pub minute: u8,
+    // local time offset in half hours
+    pub offset: i8,
+}
+
+impl ClockTime {
+    // (year, month, day) of mjd, as described in the RDS standard annex
+    pub fn date(&self) -> (u32, u8, u8) {
+        // scaled by 100 / 10000 to keep in integer
+        let mjd = self.mjd as i64 * 100;
+        let y = (mjd - 1_507_820) / 36_525;
+        let y365 = y * 36_525 / 100;
+        let m = (mjd - 1_495_610 - y365 * 100) * 100 / 306_001;
+        let d = self.mjd as i64 - 14_956 - y365 - m * 306_001 / 10_000;
+        let k = if m == 14 || m == 15 { 1 } else { 0 };
+        ((y + k + 1900) as u32, (m - 1 - k * 12) as u8, d as u8)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct RdsData {
+    pub pi: Option<u16>,
+    // program service name
+    pub ps: [u8; 8],
+    pub radio_text: [u8; 64],
+    pub clock: Option<ClockTime>,
+
+    // A/B flag of radio text; text is cleared on change
+    rt_ab: Option<bool>,
+}
+
+impl RdsData {
+    pub const fn new() -> Self {
+        Self {
+            pi: None,
+            ps: [b' '; 8],
+            radio_text: [b' '; 64],
+            clock: None,
+            rt_ab: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    // returns true if anything is changed
+    pub fn update(&mut self, group: &Group) -> bool {
+        let old = (self.pi, self.ps, self.radio_text, self.clock);
+        self.parse(group);
+        old != (self.pi, self.ps, self.radio_text, self.clock)
+    }
+
+    fn parse(&mut self, group: &Group) {
+        let Some(b) = group[1] else {
+            return;
+        };
+        let group_type = b >> 12;
+        let version_b = b & (1 << 11) != 0;
+
+        // PI is in block A, and also in C' of B version groups
+        let pi = group[0].or(if version_b { group[2] } else { None });
+        if let Some(pi) = pi {
+            if self.pi.is_some_and(|p| p != pi) {
+                // another station
+                self.clear();
+            }
+            self.pi = Some(pi);
+        }
+
+        match (group_type, version_b) {
+            (0, _) => {
+                // basic tuning and switching information
+                let Some(d) = group[3] else {
+                    return;
+                };
+                let i = (b & 3) as usize * 2;
+                self.ps[i..i + 2].copy_from_slice(&printable(d));
+            }
+            (2, _) => {
+                // radio text
+                let ab = b & (1 << 4) != 0;
+                if self.rt_ab.is_some_and(|x| x != ab) {
+                    self.radio_text = [b' '; 64];
+                }
+                self.rt_ab = Some(ab);
+
+                let addr = (b & 0xf) as usize;
+                if version_b {
+                    let Some(d) = group[3] else {
+                        return;
+                    };
+                    self.radio_text[addr * 2..addr * 2 + 2].copy_from_slice(&printable(d));
+                } else {
+                    let (Some(c), Some(d)) = (group[2], group[3]) else {
+                        return;
+                    };
+                    self.radio_text[addr * 4..addr * 4 + 2].copy_from_slice(&printable(c));
+                    self.radio_text[addr * 4 + 2..addr * 4 + 4].copy_from_slice(&printable(d));
+                }
+            }
+            (4, false) => {
+                // clock time and date
+                let (Some(c), Some(d)) = (group[2], group[3]) else {
+                    return;
+                };
+                let offset = (d & 0x1f) as i8;
+                self.clock = Some(ClockTime {
+                    mjd: ((b as u32 & 3) << 15) | (c as u32 >> 1),
+                    hour: (((c & 1) << 4) | (d >> 12)) as u8,
+                    minute: ((d >> 6) & 0x3f) as u8,
+                    offset: if d & (1 << 5) != 0 { -offset } else { offset },
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for RdsData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// two characters in a block; unsupported ones are replaced
+fn printable(v: u16) -> [u8; 2] {
+    let c = |x: u8| if (0x20..0x7f).contains(&x) { x } else { b'?' };
+    [c((v >> 8) as u8), c(v as u8)]
+}
@@ -0,0 +1,184 @@
+use crate::dsp::{fir::FirDecimator, pll::Pll, window::Window, DSPComplex};
+use crate::sdr::DS_RATIO;
+use crate::SAMPLE_RATE;
+
+mod block;
+mod group;
+pub use block::{BlockSync, Group};
+pub use group::{ClockTime, RdsData};
+
+// subcarrier is 57 / 192 of SAMPLE_RATE
+const CARRIER_PERIOD: usize = 192;
+const CARRIER_CYCLES: usize = 57;
+const RATE: u32 = (SAMPLE_RATE / DS_RATIO / DS_RATIO) as u32;
+// biphase chip rate: 2 chips per 1187.5bps bit
+const CHIP_RATE: u64 = 2_375;
+
+/*
+RDS decoder
+
+takes the discriminator output (MPX) at SAMPLE_RATE in re. the 57kHz subcarrier is mixed
+down and decimated to 12kHz, then the BPSK carrier is recovered by a PLL on the squared
+signal. chips are integrated between zero-crossing-locked clock edges, paired into
+biphase symbols and differentially decoded into bits.
+*/
+pub struct RdsDecoder {
+    // exp(-i * subcarrier phase)
+    rot: [DSPComplex; CARRIER_PERIOD],
+    idx: usize,
+
+    decimator: FirDecimator<DSPComplex, 32, DS_RATIO>,
+    filter: FirDecimator<DSPComplex, 64, DS_RATIO>,
+
+    // locks to twice the carrier phase
+    pll: Pll,
+    last_pll_phase: i32,
+    // unwrapped half of the pll phase
+    phase: i32,
+
+    // chip clock; wraps at chip edges
+    clock: u32,
+    last: i32,
+    acc: i32,
+    last_chip: i32,
+    // which of two chip alignments form a bit, and their averaged metric
+    chip_parity: bool,
+    pair_metric: [i32; 2],
+    last_symbol: bool,
+
+    sync: BlockSync,
+    data: RdsData,
+}
+
+impl RdsDecoder {
+    const CLOCK_STEP: u32 = ((CHIP_RATE << 32) / RATE as u64) as u32;
+
+    pub fn new() -> Self {
+        let mut rot = [DSPComplex::zero(); CARRIER_PERIOD];
+        for (i, x) in rot.iter_mut().enumerate() {
+            // theta = 1<<18 represents 2pi
+            let p = (i * CARRIER_CYCLES % CARRIER_PERIOD) as i32;
+            *x = DSPComplex::expi(-((p << 18) / CARRIER_PERIOD as i32));
+        }
+        Self {
+            rot,
+            idx: 0,
+            decimator: FirDecimator::lowpass(4_000, SAMPLE_RATE as u32, Window::BlackmanHarris),
+            filter: FirDecimator::lowpass(
+                2_400,
+                (SAMPLE_RATE / DS_RATIO) as u32,
+                Window::BlackmanHarris,
+            ),
+            pll: Pll::new(20, 100, RATE),
+            last_pll_phase: 0,
+            phase: 0,
+            clock: 0,
+            last: 0,
+            acc: 0,
+            last_chip: 0,
+            chip_parity: false,
+            pair_metric: [0; 2],
+            last_symbol: false,
+            sync: BlockSync::new(),
+            data: RdsData::new(),
+        }
+    }
+
+    pub fn data(&self) -> &RdsData {
+        &self.data
+    }
+
+    pub fn synced(&self) -> bool {
+        self.sync.synced()
+    }
+
+    pub fn reset(&mut self) {
+        self.sync.reset();
+        self.data.clear();
+    }
+
+    // returns true if data is updated
+    pub fn process(&mut self, mpx: &[DSPComplex]) -> bool {
+        let mut updated = false;
+        for x in mpx {
+            let r = self.rot[self.idx];
+            self.idx = if self.idx + 1 == CARRIER_PERIOD {
+                0
+            } else {
+                self.idx + 1
+            };
+            let Some(y) = self.decimator.push(r * x.re) else {
+                continue;
+            };
+            let Some(y) = self.filter.push(y) else {
+                continue;
+            };
+            if let Some(bit) = self.demod(y) {
+                if let Some(g) = self.sync.push(bit) {
+                    updated |= self.data.update(&g);
+                }
+            }
+        }
+        updated
+    }
+
+    // one sample at RATE; returns a bit if completed
+    fn demod(&mut self, y: DSPComplex) -> Option<bool> {
+        // squaring removes BPSK modulation; scale to keep precision of weak signals
+        let (re, im) = (y.re.0 as i32, y.im.0 as i32);
+        let (sr, si) = (re * re - im * im, 2 * re * im);
+        let bits = 32 - (sr.unsigned_abs() | si.unsigned_abs()).leading_zeros();
+        let sh = bits.saturating_sub(15);
+        self.pll
+            .track(DSPComplex::from_i16((sr >> sh) as i16, (si >> sh) as i16));
+
+        // half of the pll phase, unwrapped so that it does not jump by pi
+        let p = self.pll.phase();
+        let d = ((p - self.last_pll_phase) << 14) >> 14;
+        self.last_pll_phase = p;
+        self.phase = (self.phase + d / 2) & 0x3ffff;
+        let v = (y * DSPComplex::expi(-self.phase)).re.0 as i32;
+
+        // zero crossing should be on a chip edge; pull the clock
+        if (v < 0) != (self.last < 0) && v != self.last {
+            // position of crossing before this sample, in clock unit
+            let frac = (v as i64 * Self::CLOCK_STEP as i64 / (v - self.last) as i64) as i32;
+            let err = self.clock.wrapping_sub(frac as u32) as i32;
+            self.clock = self.clock.wrapping_sub((err >> 3) as u32);
+        }
+        self.last = v;
+
+        // integrate and dump
+        self.acc += v;
+        let (clock, wrapped) = self.clock.overflowing_add(Self::CLOCK_STEP);
+        self.clock = clock;
+        if !wrapped {
+            return None;
+        }
+        let chip = core::mem::take(&mut self.acc);
+
+        // biphase symbol is a pair of opposite chips; find which alignment it is
+        let s = self.last_chip - chip;
+        self.last_chip = chip;
+        self.chip_parity = !self.chip_parity;
+        let m = &mut self.pair_metric[self.chip_parity as usize];
+        *m += (s.abs() - *m) >> 4;
+        if self.pair_metric[self.chip_parity as usize]
+            < self.pair_metric[!self.chip_parity as usize]
+        {
+            return None;
+        }
+
+        // differential decoding
+        let symbol = s < 0;
+        let bit = symbol != self.last_symbol;
+        self.last_symbol = symbol;
+        Some(bit)
+    }
+}
+
+impl Default for RdsDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
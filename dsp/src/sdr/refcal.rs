@@ -0,0 +1,273 @@
+use crate::{dsp::DSPComplex, SAMPLE_RATE};
+
+/*
+reference oscillator calibration
+
+a carrier of known frequency is looked for in the spectrum (DC at N/2, as FFT makes it).
+with the Hann window, the magnitudes of the peak bin c and its larger neighbor b tell where
+the carrier is between them: b / c = (1 + d) / (2 - d), so d = (2b - c) / (b + c).
+that is only a few Hz close, and PhaseTrack takes it from there down to mHz.
+*/
+
+fn power(x: &DSPComplex) -> u32 {
+    let (re, im) = (x.re.0 as i32, x.im.0 as i32);
+    (re * re) as u32 + (im * im) as u32
+}
+
+// adds the magnitudes of spectrum to acc, to average over several
+pub fn accumulate(spectrum: &[DSPComplex], acc: &mut [u32]) {
+    for (a, x) in acc.iter_mut().zip(spectrum.iter()) {
+        *a += power(x).isqrt();
+    }
+}
+
+// position of the peak within span bins of center [Q8 bins from DC]
+pub fn peak_offset(mag: &[u32], center: i32, span: i32) -> Option<i32> {
+    let n = mag.len() as i32;
+    let lo = (center - span).max(1);
+    let hi = (center + span).min(n - 2);
+    let k = (lo..=hi).max_by_key(|&k| mag[k as usize])?;
+    let c = mag[k as usize] as i64;
+    if c == 0 {
+        return None;
+    }
+    let (l, r) = (mag[k as usize - 1] as i64, mag[k as usize + 1] as i64);
+    let b = l.max(r);
+    let d = (((2 * b - c) << 8) / (b + c)).clamp(0, 1 << 8) as i32;
+    let d = if r >= l { d } else { -d };
+    Some(((k - n / 2) << 8) + d)
+}
+
+// [Q8 bins] to [Hz] with an N-point FFT
+pub fn bins_to_hz(offset: i32, n: usize) -> i32 {
+    ((offset as i64 * SAMPLE_RATE as i64) / ((n as i64) << 8)) as i32
+}
+
+/*
+error of the reference [ppb], from a carrier expected at offset [mHz] from the LO at lo [Hz],
+found at measured [mHz]. a fast reference makes a high LO, and the carrier appears lower.
+*/
+pub fn ppb_error(measured: i64, offset: i64, lo: u32) -> i32 {
+    ((offset - measured) * 1_000_000 / lo as i64) as i32
+}
+
+// standard frequency carriers [Hz]: WWV/WWVH/BPM 2.5M to 25M. LF ones like JJY 40k/60k are
+// too low for a bin to resolve ppb
+pub const STANDARDS: [u32; 6] = [
+    2_500_000, 5_000_000, 10_000_000, 15_000_000, 20_000_000, 25_000_000,
+];
+
+// within this many bins of where the carrier should be
+pub const SEARCH_SPAN: i32 = 8;
+// times the peak is over the average of the span around its main lobe
+pub const MIN_PEAK: u64 = 2;
+// rms of the phase off the prediction [1 << 18 per turn]; a quarter of half a turn, where
+// the unwrapping slips
+pub const MAX_JITTER: i64 = 1 << 15;
+
+// why a carrier measurement is not taken
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CalFault {
+    NoCarrier,
+    // not clearly over the noise
+    Weak,
+    // moves between spectra, or its phase jumps around
+    Unstable,
+    // too far off to be the carrier; up to the caller
+    OutOfRange,
+}
+
+/*
+carrier measurement over several spectra
+
+the first spectra after tuning are let go, as the LO settles; the magnitudes of the rest
+are averaged, which keeps the ratio of the bins of a steady carrier and smooths the noise.
+a carrier is taken only if it stands out of the rest of the span, and most of the spectra
+alone have their peak next to it too.
+*/
+pub struct CarrierSearch<const N: usize> {
+    acc: [u32; N],
+    // [Hz] from the LO
+    offset: i32,
+    settle: u32,
+    frames: u32,
+    // spectra that have their peak at each bin of the span
+    hits: [u16; 2 * SEARCH_SPAN as usize + 1],
+}
+
+impl<const N: usize> CarrierSearch<N> {
+    // a carrier expected at offset [Hz] from the LO
+    pub fn new(offset: i32, settle: u32, frames: u32) -> Self {
+        Self {
+            acc: [0; N],
+            offset,
+            settle,
+            frames,
+            hits: [0; 2 * SEARCH_SPAN as usize + 1],
+        }
+    }
+
+    // bin where the carrier should be
+    fn center(&self) -> i32 {
+        N as i32 / 2 + self.offset * N as i32 / SAMPLE_RATE as i32
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    // a spectrum made with the Hann window; true when enough are taken
+    pub fn push(&mut self, spectrum: &[DSPComplex; N]) -> bool {
+        if self.settle > 0 {
+            self.settle -= 1;
+        } else if self.frames > 0 {
+            accumulate(spectrum, &mut self.acc);
+            let c = self.center();
+            let span = (c - SEARCH_SPAN).max(0)..=(c + SEARCH_SPAN).min(N as i32 - 1);
+            if let Some(k) = span.max_by_key(|&k| power(&spectrum[k as usize])) {
+                self.hits[(k - c + SEARCH_SPAN) as usize] += 1;
+            }
+            self.frames -= 1;
+        }
+        self.settle == 0 && self.frames == 0
+    }
+
+    // where the carrier is [Hz] from the LO
+    pub fn carrier(&self) -> Result<i32, CalFault> {
+        let center = self.center();
+        let p = peak_offset(&self.acc, center, SEARCH_SPAN).ok_or(CalFault::NoCarrier)?;
+        let k = N as i32 / 2 + ((p + 128) >> 8);
+        let (sum, n) = (center - SEARCH_SPAN..=center + SEARCH_SPAN)
+            .filter(|&i| (i - k).abs() > 2 && (0..N as i32).contains(&i))
+            .fold((0, 0), |(s, n), i| (s + self.acc[i as usize] as u64, n + 1));
+        let near = (k - center + SEARCH_SPAN - 1).max(0) as usize;
+        let near: u16 = self.hits.iter().skip(near).take(3).sum();
+        if (self.acc[k as usize] as u64) * n < MIN_PEAK * sum {
+            Err(CalFault::Weak)
+        } else if near * 2 <= self.hits.iter().sum() {
+            Err(CalFault::Unstable)
+        } else {
+            Ok(bins_to_hz(p, N))
+        }
+    }
+}
+
+/*
+carrier measurement by its phase
+
+each block of input is mixed down by the offset found in the spectrum, and summed into a
+phasor; what is left of the offset turns it by 2pi * df * dt from block to block. the phase is
+unwrapped with the slope so far, and fit to a line by least squares, whose slope is df.
+blocks may be skipped or partial, as they are placed by their sample time.
+*/
+pub struct PhaseTrack {
+    // mixer, 1 << 32 is one turn per sample
+    step: u32,
+    // sample time of the first block
+    start: Option<u32>,
+    // middle of the last block [samples from start], and its unwrapped phase
+    last: (i64, i64),
+    // sums for the fit of phase p [1 << 18 per turn] over t [samples]
+    n: i64,
+    st: i64,
+    sp: i64,
+    stt: i64,
+    stp: i64,
+    // sum of squares of the phase off the prediction, and how many
+    jitter: (i64, i64),
+    blocks: u32,
+}
+
+impl PhaseTrack {
+    // a carrier about offset [mHz] from the LO, followed over this many blocks
+    pub fn new(offset: i64, blocks: u32) -> Self {
+        Self {
+            step: ((offset << 32) / (SAMPLE_RATE as i64 * 1000)) as u32,
+            start: None,
+            last: (0, 0),
+            n: 0,
+            st: 0,
+            sp: 0,
+            stt: 0,
+            stp: 0,
+            jitter: (0, 0),
+            blocks,
+        }
+    }
+
+    // (numerator, denominator) of the slope of the fit
+    fn slope(&self) -> (i128, i128) {
+        let n = self.n as i128;
+        let (st, sp) = (self.st as i128, self.sp as i128);
+        (
+            n * self.stp as i128 - st * sp,
+            n * self.stt as i128 - st * st,
+        )
+    }
+
+    // buf from sample time t; true when enough blocks are taken
+    pub fn push(&mut self, t: u32, buf: &[DSPComplex]) -> bool {
+        if self.blocks == 0 {
+            return true;
+        }
+        if buf.is_empty() {
+            return false;
+        }
+        let t = t.wrapping_sub(*self.start.get_or_insert(t));
+
+        // the mixer is started right at t, and steps by its 18 bits within the block
+        let mut w = DSPComplex::expi(-((self.step.wrapping_mul(t) >> 14) as i32));
+        let dw = DSPComplex::expi(-((self.step >> 14) as i32));
+        let (mut re, mut im) = (0i64, 0i64);
+        for x in buf {
+            let (xr, xi) = (x.re.0 as i32, x.im.0 as i32);
+            let (wr, wi) = (w.re.0 as i32, w.im.0 as i32);
+            re += (xr * wr - xi * wi) as i64;
+            im += (xr * wi + xi * wr) as i64;
+            w *= dw;
+        }
+        let m = re.unsigned_abs().max(im.unsigned_abs());
+        let shift = (64 - m.leading_zeros()).saturating_sub(14);
+        let phase = DSPComplex::from_i16((re >> shift) as i16, (im >> shift) as i16)
+            .phase_cordic(16) as i64;
+
+        let tc = t as i64 + buf.len() as i64 / 2;
+        let p = if self.n == 0 {
+            phase
+        } else {
+            // within half a turn of where the fit so far goes
+            let (num, den) = self.slope();
+            let dt = (tc - self.last.0) as i128;
+            let pred = self.last.1 + if den == 0 { 0 } else { (num * dt / den) as i64 };
+            let d = (phase - pred).rem_euclid(1 << 18);
+            let d = if d >= 1 << 17 { d - (1 << 18) } else { d };
+            if den != 0 {
+                self.jitter = (self.jitter.0 + d * d, self.jitter.1 + 1);
+            }
+            pred + d
+        };
+        self.last = (tc, p);
+        self.n += 1;
+        self.st += tc;
+        self.sp += p;
+        self.stt += tc * tc;
+        self.stp += tc * p;
+
+        self.blocks -= 1;
+        self.blocks == 0
+    }
+
+    // where the carrier is [mHz] from the LO
+    pub fn carrier(&self) -> Result<i64, CalFault> {
+        let (num, den) = self.slope();
+        if den == 0 {
+            return Err(CalFault::NoCarrier);
+        }
+        let (ss, n) = self.jitter;
+        if n > 0 && ss / n > MAX_JITTER * MAX_JITTER {
+            return Err(CalFault::Unstable);
+        }
+        let mixer = (self.step as i32 as i64 * SAMPLE_RATE as i64 * 1000) >> 32;
+        Ok(mixer + (num * SAMPLE_RATE as i128 * 1000 / (den << 18)) as i64)
+    }
+}
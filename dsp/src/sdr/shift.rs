@@ -0,0 +1,118 @@
+use crate::dsp::{
+    fir::{design_lowpass, Fir, FirDecimator},
+    window::Window,
+    DSPComplex, DSPNum,
+};
+use crate::SAMPLE_RATE;
+
+use super::{DS_RATE, DS_RATIO};
+
+const DECIM_TAPS: usize = 32;
+const CHANNEL_TAPS: usize = 64;
+
+// freq shift, down sample and channel filter signal
+pub struct Shifter {
+    phase: u32,
+    freq: i32,
+    omega: i32,
+    // turns back the phase of one block
+    rewind: DSPComplex,
+    bandwidth: u32,
+
+    rot_buf: [DSPComplex; Self::INPUT_SIZE],
+
+    // anti-alias filter for 192kHz -> 48kHz
+    decimator: FirDecimator<DSPComplex, DECIM_TAPS, DS_RATIO>,
+    // channel filter at DS_RATE
+    channel: Fir<DSPComplex, CHANNEL_TAPS>,
+}
+
+impl Shifter {
+    pub const INPUT_SIZE: usize = 192;
+    pub const OUTPUT_SIZE: usize = Self::INPUT_SIZE / DS_RATIO;
+
+    pub const DEFAULT_BANDWIDTH: u32 = 10_000;
+    // -6dB point of the decimator; flat up to about +-12kHz, rejects images from 36kHz
+    const DECIM_CUTOFF: u32 = 24_000;
+
+    pub fn new() -> Self {
+        Shifter {
+            phase: 0,
+            freq: 0,
+            omega: 0,
+            rewind: DSPComplex::one(),
+            bandwidth: Self::DEFAULT_BANDWIDTH,
+            rot_buf: [DSPComplex::zero(); Self::INPUT_SIZE],
+            decimator: FirDecimator::lowpass(
+                Self::DECIM_CUTOFF,
+                SAMPLE_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+            channel: Fir::lowpass(
+                Self::DEFAULT_BANDWIDTH / 2,
+                DS_RATE as u32,
+                Window::BlackmanHarris,
+            ),
+        }
+    }
+
+    pub fn set_freq(&mut self, freq: i32) {
+        self.freq = freq;
+        // self.omega = (freq << 18) / SAMPLE_RATE as i32;
+        self.omega = freq * 512 / 375;
+
+        // NOTE: size is now small so naive approach is fine
+        for (i, x) in self.rot_buf.iter_mut().enumerate() {
+            *x = DSPComplex::expi((i as i32) * self.omega);
+        }
+        self.rewind = DSPComplex::expi(-self.omega * Self::INPUT_SIZE as i32);
+    }
+
+    pub fn bandwidth(&self) -> u32 {
+        self.bandwidth
+    }
+
+    // set channel filter bandwidth (two-sided) [Hz]
+    pub fn set_bandwidth(&mut self, bandwidth: u32) {
+        self.bandwidth = bandwidth.min(Self::DECIM_CUTOFF * 2);
+        let mut h = [DSPNum(0); CHANNEL_TAPS];
+        design_lowpass(
+            &mut h,
+            self.bandwidth / 2,
+            DS_RATE as u32,
+            Window::BlackmanHarris,
+        );
+        self.channel.set_taps(&h);
+    }
+
+    pub fn apply(
+        &mut self,
+        input: &[DSPComplex; Self::INPUT_SIZE],
+        output: &mut [DSPComplex; Self::OUTPUT_SIZE],
+    ) {
+        /*
+        rot_buf starts over at every block; the phase of the block start is put on after
+        the decimator, at a quarter of the rate. the history in the decimator is from the
+        last block, which started one block of phase earlier, and is turned back for it.
+        */
+        let p = DSPComplex::expi(self.phase as i32);
+        self.decimator.rotate(self.rewind);
+        let mut k = 0;
+        for (x, r) in input.iter().zip(self.rot_buf.iter()) {
+            if let Some(y) = self.decimator.push(x * r) {
+                output[k] = self.channel.push(y * p).unwrap();
+                k += 1;
+            }
+        }
+
+        self.phase = self
+            .phase
+            .wrapping_add_signed(self.omega * Self::INPUT_SIZE as i32);
+    }
+}
+
+impl Default for Shifter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+/*
+S-meter
+
+channel power in dBFS (see Squelch::power) is referred to the antenna: the ADC PGA gain is
+taken away, and the level at the antenna that makes full scale is added. the latter depends
+on the mixer and front end over frequency, so it is a table of bands.
+*/
+
+// (upper end of band [Hz], antenna level of full scale at 0dB PGA gain [dBm])
+// nominal; measure with a signal generator and adjust
+pub const CALIBRATION: [(u32, i32); 5] = [
+    (2_000_000, 2),
+    (10_000_000, 2),
+    (30_000_000, 3),
+    (100_000_000, 5),
+    (u32::MAX, 8),
+];
+
+// S9 [dBm], IARU: 50uV below 30MHz, 5uV above; 6dB per S unit
+pub fn s9_level(freq: u32) -> i32 {
+    if freq < 30_000_000 {
+        -73
+    } else {
+        -93
+    }
+}
+
+// power: [Q8 dBFS], adc_gain: PGA gain [0.5dB]; returns [Q8 dBm]
+pub fn dbm(power: i32, adc_gain: i8, freq: u32) -> i32 {
+    let (_, full_scale) = CALIBRATION
+        .iter()
+        .find(|(upper, _)| freq < *upper)
+        .unwrap_or(&CALIBRATION[CALIBRATION.len() - 1]);
+    power + (full_scale << 8) - ((adc_gain as i32) << 7)
+}
+
+// S unit (0..=9) and dB over S9
+pub fn s_unit(dbm: i32, s9: i32) -> (u8, i32) {
+    let d = dbm - s9;
+    if d >= 0 {
+        (9, d)
+    } else {
+        ((9 + (d - 5) / 6).max(0) as u8, 0)
+    }
+}
+
+/*
+level with peak hold
+
+the peak stays for HOLD updates after the last rise, then falls by DECAY per update.
+*/
+pub struct SMeter {
+    // [Q8 dBm]
+    level: i32,
+    peak: i32,
+    hold: u32,
+}
+
+impl SMeter {
+    pub const HOLD: u32 = 10;
+    // [Q8 dB]
+    pub const DECAY: i32 = 2 << 8;
+
+    pub fn new() -> Self {
+        Self {
+            level: i32::MIN,
+            peak: i32::MIN,
+            hold: 0,
+        }
+    }
+
+    // [Q8 dBm]
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn peak(&self) -> i32 {
+        self.peak
+    }
+
+    pub fn update(&mut self, dbm: i32) {
+        self.level = dbm;
+        if dbm >= self.peak {
+            self.peak = dbm;
+            self.hold = Self::HOLD;
+        } else if self.hold > 0 {
+            self.hold -= 1;
+        } else {
+            self.peak = (self.peak - Self::DECAY).max(dbm);
+        }
+    }
+}
+
+impl Default for SMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
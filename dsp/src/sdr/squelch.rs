@@ -0,0 +1,133 @@
+use crate::dsp::{db10, DSPComplex};
+
+use super::DS_RATE;
+
+// full scale power (1.0^2 in Q14) in Q8 dB
+const FULL_SCALE: i32 = 28 * 771;
+const HYSTERESIS: i32 = 3 << 8;
+// mute / unmute in 10ms
+const RAMP_STEP: i32 = (1 << 14) / (DS_RATE as i32 / 100);
+
+/*
+squelch
+
+carrier squelch opens when the channel power reaches a level in dBFS.
+noise squelch is for FM; opens when the discriminator noise is suppressed (quieting) by
+the given dB. both are evaluated per block with hysteresis, and audio is ramped
+instead of cut to avoid clicks.
+*/
+pub struct Squelch {
+    // [dBFS], None to disable
+    carrier_level: Option<i32>,
+    // [dB], None to disable
+    noise_level: Option<i32>,
+
+    // smoothed measurements, Q8 dB
+    power: i32,
+    quieting: i32,
+
+    open: bool,
+    // Q14
+    gain: i32,
+}
+
+impl Squelch {
+    pub const MIN_CARRIER_LEVEL: i32 = -99;
+    pub const MAX_CARRIER_LEVEL: i32 = 0;
+    pub const MAX_NOISE_LEVEL: i32 = 30;
+
+    pub fn new() -> Self {
+        Self {
+            carrier_level: None,
+            noise_level: None,
+            power: FULL_SCALE,
+            quieting: 0,
+            open: true,
+            gain: 1 << 14,
+        }
+    }
+
+    pub fn carrier_level(&self) -> Option<i32> {
+        self.carrier_level
+    }
+
+    pub fn set_carrier_level(&mut self, level: Option<i32>) {
+        self.carrier_level = level;
+    }
+
+    pub fn noise_level(&self) -> Option<i32> {
+        self.noise_level
+    }
+
+    pub fn set_noise_level(&mut self, level: Option<i32>) {
+        self.noise_level = level;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // smoothed channel power [Q8 dBFS]
+    pub fn power(&self) -> i32 {
+        self.power
+    }
+
+    // channel power of a block, before demodulation
+    pub fn measure(&mut self, buf: &[DSPComplex]) {
+        let mut acc = 0u64;
+        for x in buf {
+            let (re, im) = (x.re.0 as i64, x.im.0 as i64);
+            acc += (re * re + im * im) as u64;
+        }
+        let p = if acc == 0 {
+            (Self::MIN_CARRIER_LEVEL - 10) << 8
+        } else {
+            db10(acc) - db10(buf.len() as u64) - FULL_SCALE
+        };
+        self.power += (p - self.power) >> 2;
+    }
+
+    // quieting of FM discriminator [Q8 dB]
+    pub fn measure_quieting(&mut self, quieting: i32) {
+        self.quieting += (quieting - self.quieting) >> 2;
+    }
+
+    // decide and mute audio; both of re and im are processed
+    // noise squelch is skipped if `fm` is false
+    pub fn apply(&mut self, buf: &mut [DSPComplex], fm: bool) {
+        // once opened, stays until the measurement falls below by HYSTERESIS
+        let h = if self.open { HYSTERESIS } else { 0 };
+        let carrier = self
+            .carrier_level
+            .is_none_or(|l| self.power >= (l << 8) - h);
+        let noise = !fm
+            || self
+                .noise_level
+                .is_none_or(|l| self.quieting >= (l << 8) - h);
+        self.open = carrier && noise;
+
+        let target = if self.open { 1 << 14 } else { 0 };
+        for x in buf.iter_mut() {
+            if self.gain == target {
+                if self.open {
+                    break;
+                }
+                *x = DSPComplex::zero();
+                continue;
+            }
+            self.gain = if self.open {
+                (self.gain + RAMP_STEP).min(target)
+            } else {
+                (self.gain - RAMP_STEP).max(target)
+            };
+            x.re.0 = ((x.re.0 as i32 * self.gain) >> 14) as i16;
+            x.im.0 = ((x.im.0 as i32 * self.gain) >> 14) as i16;
+        }
+    }
+}
+
+impl Default for Squelch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
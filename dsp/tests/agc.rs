@@ -0,0 +1,104 @@
+mod common;
+
+use common::from_f64;
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::agc::{Agc, AgcPreset},
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const DS_RATE: f64 = (SAMPLE_RATE / 4) as f64;
+const BLOCK: usize = 48;
+
+// 1kHz tone of `amp` for `ms`; returns peak of output in each 1ms block
+fn tone(agc: &mut Agc, amp: f64, ms: usize, n0: &mut usize) -> Vec<f64> {
+    let mut peaks = Vec::new();
+    for _ in 0..ms {
+        let mut buf: Vec<DSPComplex> = (0..BLOCK)
+            .map(|i| {
+                let t = 2.0 * PI * 1_000.0 * (*n0 + i) as f64 / DS_RATE;
+                from_f64(amp * t.sin(), 0.0)
+            })
+            .collect();
+        *n0 += BLOCK;
+        agc.process(&mut buf, false);
+        let p = buf
+            .iter()
+            .map(|x| (x.re.0 as f64 / common::ONE).abs())
+            .fold(0.0, f64::max);
+        peaks.push(p);
+    }
+    peaks
+}
+
+#[test]
+fn levels_to_target() {
+    for preset in [AgcPreset::Slow, AgcPreset::Fast] {
+        for amp in [0.01, 0.1, 1.0] {
+            let mut agc = Agc::new(preset.params());
+            let peaks = tone(&mut agc, amp, 200, &mut 0);
+            let p = *peaks.last().unwrap();
+            assert!((p - 0.5).abs() < 0.05, "amp {amp}: peak {p}");
+            let g = agc.gain_db() as f64 / 256.0;
+            let expected = 20.0 * (0.5 / amp).log10();
+            assert!((g - expected).abs() < 1.0, "amp {amp}: gain {g}dB");
+        }
+    }
+}
+
+#[test]
+fn gain_is_limited() {
+    let mut agc = Agc::new(AgcPreset::Fast.params());
+    tone(&mut agc, 0.0, 1_000, &mut 0);
+    assert!((agc.gain_db() as f64 / 256.0 - 40.0).abs() < 0.5);
+}
+
+#[test]
+fn attack_hang_decay() {
+    let mut n = 0;
+    let mut agc = Agc::new(AgcPreset::Slow.params());
+    tone(&mut agc, 0.1, 300, &mut n);
+
+    // attack: a 20dB step is brought back within a few time constants
+    let peaks = tone(&mut agc, 1.0, 50, &mut n);
+    assert!(peaks[0] > 1.0);
+    assert!((peaks[49] - 0.5).abs() < 0.05, "after attack {}", peaks[49]);
+
+    // hang: gain holds after the signal drops
+    let g = agc.gain_db();
+    tone(&mut agc, 0.1, 400, &mut n);
+    assert!((agc.gain_db() - g).abs() < 256, "gain moved during hang");
+
+    // decay: then recovers
+    let peaks = tone(&mut agc, 0.1, 3_000, &mut n);
+    assert!((peaks.last().unwrap() - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn off_passes_through() {
+    let mut agc = Agc::new(AgcPreset::Off.params());
+    let peaks = tone(&mut agc, 0.1, 100, &mut 0);
+    assert!((peaks.last().unwrap() - 0.1).abs() < 1e-3);
+    assert!(agc.gain_db() == 0);
+}
+
+#[test]
+fn stereo_shares_gain() {
+    let mut agc = Agc::new(AgcPreset::Fast.params());
+    for k in 0..500 {
+        let mut buf = [DSPComplex::zero(); BLOCK];
+        for (i, x) in buf.iter_mut().enumerate() {
+            let s = (2.0 * PI * i as f64 / BLOCK as f64).sin();
+            *x = from_f64(0.2 * s, 0.05 * s);
+        }
+        agc.process(&mut buf, true);
+        let (l, r) = buf.iter().fold((0.0, 0.0), |(l, r): (f64, f64), x| {
+            (l.max(x.re.0 as f64), r.max(x.im.0 as f64))
+        });
+        // after the first attack, which clips
+        if k >= 100 {
+            assert!((l / r - 4.0).abs() < 0.1, "ratio {}", l / r);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{from_f64, Rng};
+use fuwasdr_dsp::{dsp::DSPComplex, sdr::blanker::NoiseBlanker, sdr::shift::Shifter, SAMPLE_RATE};
+use std::f64::consts::PI;
+
+// carrier of `amp` at 1kHz with a little noise
+fn carrier(amp: f64, len: usize, rng: &mut Rng) -> Vec<DSPComplex> {
+    (0..len)
+        .map(|n| {
+            let p = 2.0 * PI * 1_000.0 * n as f64 / SAMPLE_RATE as f64;
+            from_f64(
+                amp * p.cos() + 0.002 * rng.next_f64(),
+                amp * p.sin() + 0.002 * rng.next_f64(),
+            )
+        })
+        .collect()
+}
+
+fn run(nb: &mut NoiseBlanker, input: &[DSPComplex]) -> Vec<DSPComplex> {
+    let mut out = input.to_vec();
+    for chunk in out.chunks_mut(Shifter::INPUT_SIZE) {
+        nb.process(chunk);
+    }
+    out
+}
+
+fn on() -> NoiseBlanker {
+    let mut nb = NoiseBlanker::new();
+    nb.set_threshold(Some(NoiseBlanker::DEFAULT_THRESHOLD));
+    nb
+}
+
+#[test]
+fn clean_signal_passes() {
+    let mut rng = Rng(5);
+    let input = carrier(0.1, Shifter::INPUT_SIZE * 200, &mut rng);
+    let mut nb = on();
+    // settle the average
+    run(&mut nb, &input);
+    assert!(run(&mut nb, &input) == input);
+
+    // off does nothing at all
+    let mut input = input;
+    input[1000] = from_f64(1.5, 1.5);
+    assert!(run(&mut NoiseBlanker::new(), &input) == input);
+}
+
+#[test]
+fn impulses_are_interpolated() {
+    let mut rng = Rng(6);
+    let clean = carrier(0.1, Shifter::INPUT_SIZE * 200, &mut rng);
+    let mut input = clean.clone();
+    // 3 sample spikes every ~5ms, some across block boundaries
+    let spikes: Vec<usize> = (1..40).map(|k| k * 957).collect();
+    for &s in &spikes {
+        for x in &mut input[s..s + 3] {
+            *x = from_f64(1.2, -1.4);
+        }
+    }
+    let mut nb = on();
+    run(&mut nb, &clean);
+    let out = run(&mut nb, &input);
+
+    let err = |a: DSPComplex, b: DSPComplex| {
+        let (ar, ai) = common::to_f64(a);
+        let (br, bi) = common::to_f64(b);
+        ((ar - br).powi(2) + (ai - bi).powi(2)).sqrt()
+    };
+    // a line across 10 samples of the carrier is close enough; runs at the end of a block
+    // are held instead, as the next sample is not known yet
+    let max_err = (0..out.len())
+        .map(|i| err(out[i], clean[i]))
+        .fold(0.0, f64::max);
+    assert!(max_err < 0.05, "max error {max_err}");
+    // untouched away from the spikes
+    let width = (NoiseBlanker::DEFAULT_WIDTH as usize * SAMPLE_RATE / 1_000_000) / 2 + 3;
+    for i in 0..out.len() {
+        if spikes.iter().all(|&s| i + width < s || i > s + width) {
+            assert!(out[i] == input[i], "sample {i} is modified");
+        }
+    }
+}
+
+#[test]
+fn follows_level() {
+    let mut rng = Rng(7);
+    let mut nb = on();
+    run(
+        &mut nb,
+        &carrier(0.005, Shifter::INPUT_SIZE * 100, &mut rng),
+    );
+    // 30dB up; blanked at first, then it is the signal
+    run(&mut nb, &carrier(0.2, Shifter::INPUT_SIZE * 20, &mut rng));
+    let input = carrier(0.2, Shifter::INPUT_SIZE * 20, &mut rng);
+    assert!(run(&mut nb, &input) == input);
+}
+
+#[test]
+fn starts_from_first_block() {
+    let mut rng = Rng(8);
+    let input = carrier(0.1, Shifter::INPUT_SIZE * 20, &mut rng);
+    let mut nb = on();
+    assert!(run(&mut nb, &input) == input);
+
+    // turned on again at another level
+    nb.set_threshold(None);
+    run(&mut nb, &carrier(0.005, Shifter::INPUT_SIZE * 20, &mut rng));
+    nb.set_threshold(Some(NoiseBlanker::DEFAULT_THRESHOLD));
+    let input = carrier(0.005, Shifter::INPUT_SIZE * 20, &mut rng);
+    let mut spiked = input.clone();
+    spiked[1000] = from_f64(0.5, 0.5);
+    let out = run(&mut nb, &spiked);
+    assert!(out[1000] != spiked[1000]);
+    assert!(out[..900] == input[..900]);
+}
+
+#[test]
+fn width_setting() {
+    let mut nb = NoiseBlanker::new();
+    nb.set_width(100);
+    assert!((nb.width() as i32 - 100).abs() <= 5);
+    nb.set_width(0);
+    assert!(nb.width() >= NoiseBlanker::MIN_WIDTH - 5);
+    nb.set_threshold(Some(100));
+    assert!(nb.threshold() == Some(NoiseBlanker::MAX_THRESHOLD));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::from_f64;
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        channel::{mix, Channel},
+        demod::DemodMethod,
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const BLOCKS: usize = 200;
+const DS_RATE: f64 = (SAMPLE_RATE / 4) as f64;
+
+// AM carriers of (offset, audio tone), 50% modulated
+fn input(stations: &[(i32, f64)]) -> Vec<DSPComplex> {
+    (0..Shifter::INPUT_SIZE * BLOCKS)
+        .map(|n| {
+            let t = n as f64 / SAMPLE_RATE as f64;
+            let (mut re, mut im) = (0.0, 0.0);
+            for &(f, tone) in stations {
+                let a = 0.2 * (1.0 + 0.5 * (2.0 * PI * tone * t).sin());
+                let p = 2.0 * PI * f as f64 * t;
+                re += a * p.cos();
+                im += a * p.sin();
+            }
+            from_f64(re, im)
+        })
+        .collect()
+}
+
+// amplitude of `tone` in the audio
+fn tone_level(audio: &[f64], tone: f64) -> f64 {
+    let (mut c, mut s) = (0.0, 0.0);
+    for (n, a) in audio.iter().enumerate() {
+        let t = 2.0 * PI * tone * n as f64 / DS_RATE;
+        c += a * t.cos();
+        s += a * t.sin();
+    }
+    2.0 * (c * c + s * s).sqrt() / audio.len() as f64
+}
+
+// L / R audio of each channel, filter transients dropped
+fn run(input: &[DSPComplex], channels: &mut [Channel]) -> Vec<(Vec<f64>, Vec<f64>)> {
+    let mut audio = vec![(Vec::new(), Vec::new()); channels.len()];
+    let mut out = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        for (ch, (l, r)) in channels.iter_mut().zip(audio.iter_mut()) {
+            ch.process(chunk.try_into().unwrap(), &mut out);
+            l.extend(out.iter().map(|x| x.re.0 as f64 / common::ONE));
+            r.extend(out.iter().map(|x| x.im.0 as f64 / common::ONE));
+        }
+    }
+    audio
+        .into_iter()
+        .map(|(l, r)| (l[l.len() / 4..].to_vec(), r[r.len() / 4..].to_vec()))
+        .collect()
+}
+
+#[test]
+fn channels_are_independent() {
+    let signal = input(&[(-40_000, 400.0), (30_000, 1_000.0)]);
+    let mut channels = [Channel::new(), Channel::new()];
+    channels[0].set_freq(-40_000);
+    channels[1].set_freq(30_000);
+    let audio = run(&signal, &mut channels);
+
+    let (a, b) = (&audio[0].0, &audio[1].0);
+    assert!(tone_level(a, 400.0) > 0.05, "A: {}", tone_level(a, 400.0));
+    assert!(tone_level(a, 1_000.0) < 1e-3);
+    assert!(
+        tone_level(b, 1_000.0) > 0.05,
+        "B: {}",
+        tone_level(b, 1_000.0)
+    );
+    assert!(tone_level(b, 400.0) < 1e-3);
+
+    // mono is on both sides
+    assert!(audio[0].0 == audio[0].1);
+}
+
+#[test]
+fn squelch_per_channel() {
+    let signal = input(&[(-40_000, 400.0)]);
+    let mut channels = [Channel::new(), Channel::new()];
+    channels[0].set_freq(-40_000);
+    channels[1].set_freq(30_000);
+    for ch in channels.iter_mut() {
+        ch.squelch.set_carrier_level(Some(-40));
+    }
+    let audio = run(&signal, &mut channels);
+    assert!(channels[0].squelch.is_open());
+    assert!(!channels[1].squelch.is_open());
+    assert!(audio[1].0.iter().all(|x| *x == 0.0));
+}
+
+#[test]
+fn method_per_channel() {
+    let mut channels = [Channel::new(), Channel::new()];
+    channels[1].set_method(DemodMethod::NFM);
+    assert!(channels[0].method() == DemodMethod::AM);
+    assert!(channels[1].method() == DemodMethod::NFM);
+}
+
+#[test]
+fn mix_saturates() {
+    let mut a = [from_f64(1.5, -1.5), from_f64(0.25, 0.0)];
+    mix(&mut a, &[from_f64(1.5, -1.5), from_f64(0.25, 0.5)]);
+    assert!(a[0] == DSPComplex::from_i16(i16::MAX, i16::MIN));
+    assert!(a[1] == from_f64(0.5, 0.5));
+}
+
+#[test]
+fn notches_remove_whistle() {
+    // a station with a tone, and a carrier 1kHz off beating with it
+    let signal = input(&[(-40_000, 400.0), (-39_000, 0.0)]);
+    let mut channels = [Channel::new(), Channel::new(), Channel::new()];
+    for ch in channels.iter_mut() {
+        ch.set_freq(-40_000);
+        ch.agc.set_params(None);
+    }
+    channels[1].set_notch(Some(1_000));
+    channels[2].set_auto_notch(true);
+    let audio = run(&signal, &mut channels);
+
+    let (plain, manual, auto) = (&audio[0].0, &audio[1].0, &audio[2].0);
+    let whistle = tone_level(plain, 1_000.0);
+    assert!(whistle > 0.05);
+    assert!(tone_level(manual, 1_000.0) < whistle / 10.0);
+    let r = tone_level(manual, 400.0) / tone_level(plain, 400.0);
+    assert!((0.9..1.1).contains(&r), "{}", r);
+    // a steady tone of the station is taken too; see notch.rs for noise-like audio
+    assert!(tone_level(auto, 1_000.0) < whistle / 5.0);
+}
@@ -0,0 +1,67 @@
+// shared helpers for host tests; reference values are computed in f64
+#![allow(dead_code)]
+
+use fuwasdr_dsp::dsp::{DSPComplex, DSPNum};
+use std::f64::consts::PI;
+
+pub const ONE: f64 = (1 << DSPNum::FIXED_POINT) as f64;
+
+pub fn to_f64(c: DSPComplex) -> (f64, f64) {
+    (c.re.0 as f64 / ONE, c.im.0 as f64 / ONE)
+}
+
+pub fn from_f64(re: f64, im: f64) -> DSPComplex {
+    DSPComplex::from_i16((re * ONE).round() as i16, (im * ONE).round() as i16)
+}
+
+// complex tone: amp * exp(2pi i f n / fs)
+pub fn tone(freq: f64, fs: f64, amp: f64, len: usize) -> Vec<DSPComplex> {
+    (0..len)
+        .map(|n| {
+            let t = 2.0 * PI * freq * n as f64 / fs;
+            from_f64(amp * t.cos(), amp * t.sin())
+        })
+        .collect()
+}
+
+// average frequency of complex signal, measured from accumulated phase difference
+pub fn measure_freq(buf: &[DSPComplex], fs: f64) -> f64 {
+    let mut acc = 0.0;
+    for w in buf.windows(2) {
+        let (ar, ai) = to_f64(w[0]);
+        let (br, bi) = to_f64(w[1]);
+        // arg(b * conj(a))
+        acc += (bi * ar - br * ai).atan2(br * ar + bi * ai);
+    }
+    acc / (buf.len() - 1) as f64 * fs / (2.0 * PI)
+}
+
+// normalized correlation between two real sequences
+pub fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let ma = a.iter().sum::<f64>() / a.len() as f64;
+    let mb = b.iter().sum::<f64>() / b.len() as f64;
+    let mut ab = 0.0;
+    let mut aa = 0.0;
+    let mut bb = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        ab += (x - ma) * (y - mb);
+        aa += (x - ma) * (x - ma);
+        bb += (y - mb) * (y - mb);
+    }
+    ab / (aa * bb).sqrt()
+}
+
+// deterministic pseudo random generator (xorshift32)
+pub struct Rng(pub u32);
+impl Rng {
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+    // uniform in [-1, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        self.next_u32() as f64 / (1u64 << 31) as f64 - 1.0
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{to_f64, Rng, ONE};
+use fuwasdr_dsp::dsp::DSPComplex;
+use std::f64::consts::PI;
+
+// angle unit used by expi/phase: 1 << 18 == 2pi
+const FULL_TURN: f64 = (1 << 18) as f64;
+
+#[test]
+fn expi_matches_sincos() {
+    let mut max_err: f64 = 0.0;
+    for theta in (0..(1 << 18)).step_by(37) {
+        let (re, im) = to_f64(DSPComplex::expi(theta));
+        let t = theta as f64 / FULL_TURN * 2.0 * PI;
+        max_err = max_err.max((re - t.cos()).abs()).max((im - t.sin()).abs());
+    }
+    // a few LSBs of rounding through the table products
+    assert!(max_err * ONE < 24.0, "max error {} LSB", max_err * ONE);
+}
+
+#[test]
+fn expi_wraps_and_negative() {
+    for theta in [-12345, -(1 << 16), -1, 5, 70000] {
+        let a = DSPComplex::expi(theta);
+        let b = DSPComplex::expi(theta + (1 << 18));
+        assert!(a == b, "theta {}", theta);
+    }
+}
+
+#[test]
+fn phase_of_axes() {
+    let v = 8000;
+    assert_eq!(DSPComplex::from_i16(v, 0).phase(), 0);
+    assert_eq!(DSPComplex::from_i16(0, 0).phase(), 0);
+    let q = 1 << 16;
+    assert!((DSPComplex::from_i16(0, v).phase() - q).abs() <= 1 << 13);
+    assert!((DSPComplex::from_i16(0, -v).phase() + q).abs() <= 1 << 13);
+    assert!((DSPComplex::from_i16(-v, 1).phase().abs() - 2 * q).abs() <= 1 << 13);
+}
+
+#[test]
+fn phase_accuracy() {
+    // phase() only resolves 3 bits per quadrant; the result is never more than one step off
+    const STEP: f64 = (1 << 13) as f64;
+    let mut rng = Rng(0x1234_5678);
+    for _ in 0..10000 {
+        let t = rng.next_f64() * PI;
+        let r = 0.1 + 0.8 * (rng.next_f64() + 1.0) / 2.0;
+        let c = common::from_f64(r * t.cos(), r * t.sin());
+        let (re, im) = to_f64(c);
+        let expected = im.atan2(re) / (2.0 * PI) * FULL_TURN;
+
+        let mut err = c.phase() as f64 - expected;
+        if err > FULL_TURN / 2.0 {
+            err -= FULL_TURN;
+        }
+        if err < -FULL_TURN / 2.0 {
+            err += FULL_TURN;
+        }
+        assert!(
+            err.abs() <= STEP + 8.0,
+            "phase({}, {}): err {}",
+            re,
+            im,
+            err
+        );
+    }
+}
+
+#[test]
+fn fast_abs_accuracy() {
+    let mut rng = Rng(42);
+    for _ in 0..10000 {
+        let c = common::from_f64(rng.next_f64() * 0.7, rng.next_f64() * 0.7);
+        let (re, im) = to_f64(c);
+        let expected = (re * re + im * im).sqrt() * ONE;
+        let f = c.fast_abs().0 as f64;
+        assert!(
+            (f - expected).abs() <= expected * 0.07 + 1.0,
+            "fast_abs {} vs {}",
+            f,
+            expected
+        );
+    }
+}
+
+#[test]
+fn phase_cordic_accuracy() {
+    let mut rng = Rng(0x8765_4321);
+    for iterations in [4, 8, 12, 16] {
+        // last step of CORDIC, plus rounding of the input
+        let bound = (2.0f64).powi(1 - iterations as i32).atan() / (2.0 * PI) * FULL_TURN + 4.0;
+        let mut max_err: f64 = 0.0;
+        for _ in 0..10000 {
+            let t = rng.next_f64() * PI;
+            let r = 0.1 + 0.8 * (rng.next_f64() + 1.0) / 2.0;
+            let c = common::from_f64(r * t.cos(), r * t.sin());
+            let (re, im) = to_f64(c);
+            let expected = im.atan2(re) / (2.0 * PI) * FULL_TURN;
+            let err = (c.phase_cordic(iterations) as f64 - expected + FULL_TURN * 1.5)
+                .rem_euclid(FULL_TURN)
+                - FULL_TURN / 2.0;
+            max_err = max_err.max(err.abs());
+        }
+        assert!(
+            max_err <= bound,
+            "{iterations} iterations: max error {max_err}, bound {bound}"
+        );
+    }
+    assert_eq!(DSPComplex::from_i16(0, 0).phase_cordic(12), 0);
+    // on the negative real axis, either side of the wrap
+    let p = DSPComplex::from_i16(-8000, 0).phase_cordic(12);
+    assert!((p.abs() - (1 << 17)).abs() <= 32, "{p}");
+}
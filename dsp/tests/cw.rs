@@ -0,0 +1,88 @@
+mod common;
+
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        demod::{CwDemod, Demodulator},
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+
+const BLOCKS: usize = 400;
+const TUNE: i32 = 25_000;
+const DS_RATE: f64 = (SAMPLE_RATE / 4) as f64;
+
+// audio of a carrier at TUNE + offset, filter transients dropped
+fn audio(offset: i32, pitch: u32, width: u32) -> Vec<f64> {
+    let input = common::tone(
+        (TUNE + offset) as f64,
+        SAMPLE_RATE as f64,
+        0.5,
+        Shifter::INPUT_SIZE * BLOCKS,
+    );
+    let mut cw = CwDemod::new();
+    cw.set_pitch(pitch);
+    cw.set_width(width);
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-TUNE);
+
+    let mut audio = Vec::new();
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        shifter.apply(chunk.try_into().unwrap(), &mut buf);
+        cw.demod(&mut buf);
+        audio.extend(buf.iter().map(|c| c.re.0 as f64 / common::ONE));
+    }
+    audio.split_off(audio.len() / 4)
+}
+
+fn db(audio: &[f64]) -> f64 {
+    let rms = (audio.iter().map(|a| a * a).sum::<f64>() / audio.len() as f64).sqrt();
+    // full level is a 0.5 amplitude sinusoid
+    20.0 * (rms / (0.5 / 2f64.sqrt())).log10()
+}
+
+// frequency from rising zero crossings
+fn audio_freq(audio: &[f64]) -> f64 {
+    let cross: Vec<f64> = audio
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0] < 0.0 && w[1] >= 0.0)
+        .map(|(i, w)| i as f64 + w[0] / (w[0] - w[1]))
+        .collect();
+    let n = cross.len() - 1;
+    n as f64 * DS_RATE / (cross[n] - cross[0])
+}
+
+#[test]
+fn carrier_beats_at_pitch() {
+    for pitch in [400, 700, 1_000] {
+        let a = audio(0, pitch, 500);
+        let f = audio_freq(&a);
+        assert!((f - pitch as f64).abs() < 1.0, "{}: {} Hz", pitch, f);
+        let d = db(&a);
+        assert!(d.abs() < 1.5, "{}: {} dB", pitch, d);
+    }
+}
+
+#[test]
+fn offset_carrier_shifts_beat_note() {
+    let f = audio_freq(&audio(-60, 700, 250));
+    assert!((f - 640.0).abs() < 1.0, "{} Hz", f);
+}
+
+#[test]
+fn cw_width() {
+    for width in [250, 500] {
+        let d = db(&audio(width as i32 / 4, 700, width));
+        assert!(d > -3.0, "{}: {} dB", width, d);
+    }
+    let d = db(&audio(300, 700, 250));
+    assert!(d < -40.0, "250: {} dB", d);
+    let d = db(&audio(500, 700, 500));
+    assert!(d < -40.0, "500: {} dB", d);
+    // opposite sideband is also rejected
+    let d = db(&audio(-1_400, 700, 500));
+    assert!(d < -40.0, "image: {} dB", d);
+}
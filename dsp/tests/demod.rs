@@ -0,0 +1,179 @@
+mod common;
+
+use common::{correlation, from_f64, ONE};
+use fuwasdr_dsp::{
+    dsp::{fir::FirDecimator, window::Window, DSPComplex, DSPNum},
+    sdr::demod::{AmDemod, DemodMethod, Demodulator, FmDemod, MODES},
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+#[test]
+fn am_envelope() {
+    const FS: f64 = 48_000.0;
+    // 1kHz tone, 50% modulation, on a carrier with arbitrary phase rotation
+    let envelope: Vec<f64> = (0..960)
+        .map(|n| 0.4 * (1.0 + 0.5 * (2.0 * PI * 1000.0 * n as f64 / FS).cos()))
+        .collect();
+    let mut buf: Vec<DSPComplex> = envelope
+        .iter()
+        .enumerate()
+        .map(|(n, &a)| {
+            let t = 2.0 * PI * 150.0 * n as f64 / FS + 0.3;
+            from_f64(a * t.cos(), a * t.sin())
+        })
+        .collect();
+
+    AmDemod.demod(&mut buf);
+
+    for (c, &e) in buf.iter().zip(&envelope) {
+        assert_eq!(c.im.0, 0);
+        let a = c.re.0 as f64 / ONE;
+        assert!((a - e).abs() < e * 0.07, "{} vs {}", a, e);
+    }
+}
+
+#[test]
+fn fm_constant_offset() {
+    for f in [-40_000.0, -5_000.0, 1_000.0, 25_000.0] {
+        let mut buf = common::tone(f, SAMPLE_RATE as f64, 0.7, 1920);
+        FmDemod::new().demod(&mut buf);
+
+        // output unit: 1 << 16 == one turn per sample
+        let expected = f / SAMPLE_RATE as f64 * 65536.0;
+        // skip first sample, which is relative to the previous call
+        let mean = buf[1..].iter().map(|c| c.re.0 as f64).sum::<f64>() / (buf.len() - 1) as f64;
+        assert!(
+            (mean - expected).abs() < 8.0,
+            "{}Hz: {} vs {}",
+            f,
+            mean,
+            expected
+        );
+    }
+}
+
+#[test]
+fn fm_tone() {
+    const FS: f64 = SAMPLE_RATE as f64;
+    const DEV: f64 = 20_000.0;
+    const FM: f64 = 1_000.0;
+    let len = 192 * 20;
+
+    // phase is the integral of the instantaneous frequency
+    let mut buf: Vec<DSPComplex> = (0..len)
+        .map(|n| {
+            let t = 2.0 * PI * FM * n as f64 / FS;
+            let p = DEV / FM * t.sin();
+            from_f64(0.7 * p.cos(), 0.7 * p.sin())
+        })
+        .collect();
+    FmDemod::new().demod(&mut buf);
+
+    // decimate by 4 the same way as core1 does
+    let mut decimator = FirDecimator::<DSPNum, 32, 4>::lowpass(15_000, FS as u32, Window::Hann);
+    let audio: Vec<f64> = buf
+        .iter()
+        .filter_map(|x| decimator.push(x.re))
+        .map(|y| y.0 as f64)
+        .skip(8)
+        .collect();
+    // group delay of the decimator is 15.5 samples
+    let reference: Vec<f64> = (8..8 + audio.len())
+        .map(|i| (2.0 * PI * FM * ((4 * i + 3) as f64 - 15.5) / FS).cos())
+        .collect();
+
+    let r = correlation(&audio, &reference);
+    assert!(r > 0.98, "correlation {}", r);
+}
+
+// SINAD of the discriminator output for a 1kHz tone at 20kHz deviation [dB]
+fn fm_sinad(discriminator: impl Fn(&mut [DSPComplex])) -> f64 {
+    const FS: f64 = SAMPLE_RATE as f64;
+    const DEV: f64 = 20_000.0;
+    const FM: f64 = 1_000.0;
+    let mut buf: Vec<DSPComplex> = (0..192 * 100)
+        .map(|n| {
+            let p = DEV / FM * (2.0 * PI * FM * n as f64 / FS).sin();
+            from_f64(0.7 * p.cos(), 0.7 * p.sin())
+        })
+        .collect();
+    discriminator(&mut buf);
+
+    // least squares fit of the tone and DC; the rest is noise and distortion
+    let audio: Vec<f64> = buf[1..].iter().map(|x| x.re.0 as f64).collect();
+    let n = audio.len() as f64;
+    let w = |i: usize| 2.0 * PI * FM * (i + 1) as f64 / FS;
+    let (mut c, mut s) = (0.0, 0.0);
+    for (i, a) in audio.iter().enumerate() {
+        c += a * w(i).cos();
+        s += a * w(i).sin();
+    }
+    let (c, s) = (2.0 * c / n, 2.0 * s / n);
+    let dc = audio.iter().sum::<f64>() / n;
+    let residual = audio
+        .iter()
+        .enumerate()
+        .map(|(i, a)| a - dc - c * w(i).cos() - s * w(i).sin())
+        .map(|r| r * r)
+        .sum::<f64>()
+        / n;
+    10.0 * ((c * c + s * s) / 2.0 / residual).log10()
+}
+
+#[test]
+fn fm_sinad_improves_with_precision() {
+    // the former discriminator, on the 3 step phase()
+    let coarse = fm_sinad(|buf| {
+        let mut last = 0;
+        for b in buf {
+            let p = b.phase() >> 2;
+            *b = DSPComplex::from_i16(p.wrapping_sub(last) as i16, 0);
+            last = p;
+        }
+    });
+    let sinad = |precision| fm_sinad(|buf| FmDemod::with_precision(precision).demod(buf));
+    let (low, default) = (sinad(4), sinad(FmDemod::DEFAULT_PRECISION));
+    println!("SINAD: phase() {coarse:.1}dB, CORDIC 4: {low:.1}dB, default: {default:.1}dB");
+    assert!(default > coarse + 40.0);
+    assert!(default > low + 40.0);
+    assert!(default > 55.0);
+}
+
+#[test]
+fn fm_state_is_per_instance() {
+    let input = common::tone(10_000.0, SAMPLE_RATE as f64, 0.7, 384);
+    let mut whole = input.clone();
+    FmDemod::new().demod(&mut whole);
+
+    let mut a = FmDemod::new();
+    let mut x0 = input[..192].to_vec();
+    a.demod(&mut x0);
+    // a different signal on another instance in between
+    FmDemod::new().demod(&mut common::tone(-30_000.0, SAMPLE_RATE as f64, 0.7, 192));
+    // continues from the end of the first block
+    let mut x1 = input[192..].to_vec();
+    a.demod(&mut x1);
+
+    assert!(x0.iter().chain(&x1).eq(whole.iter()));
+}
+
+#[test]
+fn mode_registry() {
+    assert_eq!(MODES.len(), DemodMethod::METHOD_COUNT as usize);
+    for (i, m) in MODES.iter().enumerate() {
+        assert!(m.method as usize == i);
+        assert!(DemodMethod::from_u8(i as u8) == Some(m.method));
+        assert!(m.method.mode().name == m.name);
+    }
+    assert!(DemodMethod::from_u8(DemodMethod::METHOD_COUNT).is_none());
+
+    // rotation visits every method and wraps around both ways
+    let mut m = DemodMethod::AM;
+    for _ in 0..MODES.len() {
+        m = m.rotate(1);
+    }
+    assert!(m == DemodMethod::AM);
+    assert!(DemodMethod::AM.rotate(-1) == MODES[MODES.len() - 1].method);
+    assert!(DemodMethod::AM.rotate(MODES.len() as i32 + 2) == MODES[2].method);
+}
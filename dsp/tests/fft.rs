@@ -0,0 +1,128 @@
+mod common;
+
+use common::{from_f64, to_f64, Rng, ONE};
+use fuwasdr_dsp::dsp::{fft::FFT, window::Window, DSPComplex};
+use std::f64::consts::PI;
+
+// reference: DFT / N, with DC moved to the center bin as fft() does
+fn dft(input: &[DSPComplex]) -> Vec<(f64, f64)> {
+    let n_ = input.len();
+    let x: Vec<_> = input.iter().map(|&c| to_f64(c)).collect();
+    (0..n_)
+        .map(|k| {
+            let k = (k + n_ / 2) % n_;
+            let mut acc = (0.0, 0.0);
+            for (n, (re, im)) in x.iter().enumerate() {
+                let t = -2.0 * PI * ((k * n) % n_) as f64 / n_ as f64;
+                acc.0 += re * t.cos() - im * t.sin();
+                acc.1 += re * t.sin() + im * t.cos();
+            }
+            (acc.0 / n_ as f64, acc.1 / n_ as f64)
+        })
+        .collect()
+}
+
+fn max_error(buf: &[DSPComplex], reference: &[(f64, f64)]) -> f64 {
+    buf.iter()
+        .zip(reference)
+        .map(|(&c, r)| {
+            let (re, im) = to_f64(c);
+            (re - r.0).abs().max((im - r.1).abs())
+        })
+        .fold(0.0, f64::max)
+}
+
+fn power_db(c: DSPComplex) -> f64 {
+    let (re, im) = to_f64(c);
+    10.0 * (re * re + im * im + 1e-12).log10()
+}
+
+fn check_noise<const N: usize>(seed: u32) {
+    let mut rng = Rng(seed);
+    let input: Vec<_> = (0..N)
+        .map(|_| from_f64(rng.next_f64() * 0.9, rng.next_f64() * 0.9))
+        .collect();
+    let reference = dft(&input);
+
+    let fft = FFT::<N>::new(Window::Rectangular);
+    let mut buf = [DSPComplex::zero(); N];
+    buf.copy_from_slice(&input);
+    fft.fft(&mut buf);
+
+    let err = max_error(&buf, &reference);
+    assert!(err * ONE < 8.0, "N={}: max error {} LSB", N, err * ONE);
+}
+
+#[test]
+fn fft_matches_dft_on_noise() {
+    check_noise::<64>(1);
+    check_noise::<256>(0xdead_beef);
+    check_noise::<512>(3);
+    check_noise::<2048>(4);
+}
+
+#[test]
+fn fft_tone_bins() {
+    const N: usize = 256;
+    let fft = FFT::<N>::new(Window::Rectangular);
+    for bin in [-100i32, -17, 0, 1, 64, 127] {
+        let input = common::tone(bin as f64, N as f64, 0.8, N);
+        let reference = dft(&input);
+
+        let mut buf = [DSPComplex::zero(); N];
+        buf.copy_from_slice(&input);
+        fft.fft(&mut buf);
+
+        let err = max_error(&buf, &reference);
+        assert!(err * ONE < 8.0, "bin {}: max error {} LSB", bin, err * ONE);
+
+        let peak = (0..N)
+            .max_by_key(|&i| buf[i].re.0.unsigned_abs() as u32 + buf[i].im.0.unsigned_abs() as u32)
+            .unwrap();
+        assert_eq!(peak as i32, bin + N as i32 / 2);
+    }
+}
+
+#[test]
+fn window_shapes() {
+    const N: usize = 128;
+    for w in Window::ALL {
+        let mut buf = [fuwasdr_dsp::dsp::DSPNum(0); N];
+        w.fill(&mut buf);
+        // all windows are normalized to 1 at the center
+        let c = buf[N / 2].0 as f64 / ONE;
+        assert!((c - 1.0).abs() < 2.0 / ONE, "center {}", c);
+        // symmetric around N/2
+        for i in 1..N / 2 {
+            assert!((buf[N / 2 - i].0 - buf[N / 2 + i].0).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn window_reduces_leakage() {
+    const N: usize = 512;
+    // strong carrier halfway between bins; worst case for leakage
+    let input = common::tone(40.5, N as f64, 0.9, N);
+
+    // far skirt level relative to the peak, at least 20 bins away
+    let skirt = |w: Window| {
+        let fft = FFT::<N>::new(w);
+        let mut buf = [DSPComplex::zero(); N];
+        buf.copy_from_slice(&input);
+        fft.process(&mut buf);
+        let peak = power_db(buf[N / 2 + 40]).max(power_db(buf[N / 2 + 41]));
+        let far = (0..N)
+            .filter(|&i| (i as i32 - (N / 2 + 40) as i32).abs() > 20)
+            .map(|i| power_db(buf[i]))
+            .fold(f64::MIN, f64::max);
+        far - peak
+    };
+
+    let rect = skirt(Window::Rectangular);
+    assert!(rect > -45.0, "rect {}", rect);
+    for w in [Window::Hann, Window::BlackmanHarris, Window::FlatTop] {
+        let s = skirt(w);
+        assert!(s < rect - 15.0, "skirt {} dB", s);
+    }
+}
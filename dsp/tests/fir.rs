@@ -0,0 +1,125 @@
+mod common;
+
+use common::{from_f64, to_f64, Rng};
+use fuwasdr_dsp::dsp::{
+    fir::{design_hilbert, design_lowpass, Fir, FirDecimator, FirInterpolator},
+    window::Window,
+    DSPComplex, DSPNum,
+};
+use std::f64::consts::PI;
+
+// frequency response of real taps at f / rate
+fn response_db(h: &[DSPNum], f: f64, rate: f64) -> f64 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for (n, v) in h.iter().enumerate() {
+        let t = -2.0 * PI * f / rate * n as f64;
+        re += v.0 as f64 * t.cos();
+        im += v.0 as f64 * t.sin();
+    }
+    20.0 * ((re * re + im * im).sqrt() / common::ONE).log10()
+}
+
+#[test]
+fn hilbert_design() {
+    let mut h = [DSPNum(0); 65];
+    design_hilbert(&mut h, Window::BlackmanHarris);
+    for i in 0..32 {
+        assert_eq!(h[i].0, -h[64 - i].0, "antisymmetric");
+    }
+    for f in [500.0, 1_000.0, 3_000.0, 5_500.0] {
+        assert!(response_db(&h, f, 12_000.0).abs() < 0.5, "{} Hz", f);
+    }
+}
+
+#[test]
+fn lowpass_design() {
+    let mut h = [DSPNum(0); 64];
+    design_lowpass(&mut h, 5_000, 48_000, Window::BlackmanHarris);
+
+    let sum: i32 = h.iter().map(|v| v.0 as i32).sum();
+    assert!((sum - (1 << 14)).abs() <= 32, "dc gain {}", sum);
+    for i in 0..32 {
+        assert_eq!(h[i].0, h[63 - i].0, "symmetric");
+    }
+
+    assert!(response_db(&h, 5_000.0, 48_000.0).abs() < 7.0);
+    for f in [0.0, 1_000.0, 2_000.0] {
+        assert!(response_db(&h, f, 48_000.0).abs() < 0.2, "{} Hz", f);
+    }
+    for f in [9_000.0, 12_000.0, 20_000.0, 24_000.0] {
+        assert!(response_db(&h, f, 48_000.0) < -60.0, "{} Hz", f);
+    }
+}
+
+#[test]
+fn narrow_lowpass_design() {
+    // CW width filters need precision with tiny taps
+    let mut h = [DSPNum(0); 127];
+    design_lowpass(&mut h, 250, 48_000, Window::Hann);
+    assert!(response_db(&h, 0.0, 48_000.0).abs() < 0.1);
+    assert!(response_db(&h, 2_000.0, 48_000.0) < -40.0);
+}
+
+#[test]
+fn polyphase_equals_direct_convolution() {
+    const TAPS: usize = 24;
+    const R: usize = 4;
+    let mut h = [DSPNum(0); TAPS];
+    design_lowpass(&mut h, 20_000, 192_000, Window::Hann);
+
+    let mut rng = Rng(7);
+    let input: Vec<DSPComplex> = (0..400)
+        .map(|_| from_f64(rng.next_f64() * 0.9, rng.next_f64() * 0.9))
+        .collect();
+
+    let mut dec = FirDecimator::<DSPComplex, TAPS, R>::new(&h);
+    let mut out = vec![DSPComplex::zero(); input.len() / R];
+    assert_eq!(dec.process(&input, &mut out), out.len());
+
+    for (m, o) in out.iter().enumerate() {
+        let n = m * R + R - 1;
+        let (mut re, mut im) = (0i32, 0i32);
+        for (k, t) in h.iter().enumerate() {
+            if k <= n {
+                re += input[n - k].re.0 as i32 * t.0 as i32;
+                im += input[n - k].im.0 as i32 * t.0 as i32;
+            }
+        }
+        let round = |a: i32| ((a + (1 << 13)) >> 14) as i16;
+        assert!(o.re.0 == round(re) && o.im.0 == round(im), "output {}", m);
+    }
+}
+
+#[test]
+fn fir_real_samples() {
+    let mut fir = Fir::<DSPNum, 32>::lowpass(3_000, 48_000, Window::Hann);
+    // DC passes with unity gain
+    let mut y = DSPNum(0);
+    for _ in 0..64 {
+        y = fir.push(DSPNum(8000)).unwrap();
+    }
+    assert!((y.0 - 8000).abs() <= 4, "{}", y.0);
+
+    // and high frequency is removed
+    let mut peak: f64 = 0.0;
+    for n in 0..256 {
+        let x = (PI * 0.9 * n as f64).cos() * 0.5;
+        let y = fir.push(from_f64(x, 0.0).re).unwrap();
+        if n > 64 {
+            peak = peak.max(to_f64(DSPComplex::from(y)).0.abs());
+        }
+    }
+    assert!(peak < 0.5 * 0.01, "{}", peak);
+}
+
+#[test]
+fn interpolator_keeps_level() {
+    let mut interp = FirInterpolator::<DSPNum, 32, 4>::lowpass(6_000, 48_000, Window::Hann);
+    let mut out = [DSPNum(0); 4];
+    for _ in 0..16 {
+        interp.push(DSPNum(6000), &mut out);
+    }
+    for y in out {
+        assert!((y.0 - 6000).abs() <= 8, "{}", y.0);
+    }
+}
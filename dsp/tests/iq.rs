@@ -0,0 +1,105 @@
+mod common;
+
+use common::{from_f64, Rng, ONE};
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::iq::{IqCorrection, IqMode},
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const BLOCK: usize = 384;
+const TONE: f64 = 20_000.0;
+
+// a tone at TONE and some noise, through an LO with imbalance, plus DC
+fn input(gain: f64, phase: f64, len: usize) -> Vec<DSPComplex> {
+    let mut rng = Rng(9);
+    (0..len)
+        .map(|n| {
+            let t = 2.0 * PI * TONE * n as f64 / SAMPLE_RATE as f64;
+            let (re, im) = (
+                0.3 * t.cos() + 0.01 * rng.next_f64(),
+                0.3 * t.sin() + 0.01 * rng.next_f64(),
+            );
+            // Q leaks I by the phase error
+            let q = gain * (im * phase.cos() + re * phase.sin());
+            from_f64(re + 0.05, q - 0.03)
+        })
+        .collect()
+}
+
+fn run(iq: &mut IqCorrection, input: &[DSPComplex]) -> Vec<DSPComplex> {
+    let mut buf = input.to_vec();
+    for chunk in buf.chunks_mut(BLOCK) {
+        iq.process(chunk);
+    }
+    buf
+}
+
+// amplitude at freq
+fn level(buf: &[DSPComplex], freq: f64) -> f64 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for (n, x) in buf.iter().enumerate() {
+        let t = -2.0 * PI * freq * n as f64 / SAMPLE_RATE as f64;
+        let (a, b) = (x.re.0 as f64 / ONE, x.im.0 as f64 / ONE);
+        re += a * t.cos() - b * t.sin();
+        im += a * t.sin() + b * t.cos();
+    }
+    (re * re + im * im).sqrt() / buf.len() as f64
+}
+
+// image rejection [dB]
+fn rejection(buf: &[DSPComplex]) -> f64 {
+    20.0 * (level(buf, TONE) / level(buf, -TONE)).log10()
+}
+
+#[test]
+fn track_corrects_imbalance() {
+    let x = input(1.05, 3f64.to_radians(), BLOCK * 2_000);
+    let before = rejection(&x);
+    let mut iq = IqCorrection::new();
+    let out = run(&mut iq, &x);
+    let out = &out[out.len() / 2..];
+    let after = rejection(out);
+    assert!(before < 35.0, "{}", before);
+    assert!(after > 55.0, "{} -> {}", before, after);
+    // DC is gone
+    assert!(level(out, 0.0) < 1e-3, "{}", level(out, 0.0));
+}
+
+#[test]
+fn hold_keeps_coefficients() {
+    let x = input(1.05, 3f64.to_radians(), BLOCK * 2_000);
+    let mut iq = IqCorrection::new();
+    run(&mut iq, &x);
+    let (phase, gain) = iq.coefficients();
+
+    // restored into another one, as from flash
+    let mut held = IqCorrection::new();
+    held.set_mode(IqMode::Hold);
+    held.set_coefficients(phase, gain);
+    let out = run(&mut held, &x);
+    assert!(held.coefficients() == (phase, gain));
+    assert!(rejection(&out[out.len() / 2..]) > 55.0);
+}
+
+#[test]
+fn off_passes_through() {
+    let x = input(1.05, 3f64.to_radians(), BLOCK * 4);
+    let mut iq = IqCorrection::new();
+    iq.set_mode(IqMode::Off);
+    assert!(run(&mut iq, &x) == x);
+}
+
+#[test]
+fn swap_inverts_spectrum() {
+    let x = input(1.0, 0.0, BLOCK * 200);
+    for mode in IqMode::ALL {
+        let mut iq = IqCorrection::new();
+        iq.set_mode(mode);
+        iq.set_swap(true);
+        let out = run(&mut iq, &x);
+        // the tone is mirrored to -TONE
+        assert!(rejection(&out[out.len() / 2..]) < -30.0);
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{correlation, from_f64, Rng};
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        demod::{Demodulator, NfmDemod},
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const BLOCKS: usize = 200;
+const TUNE: i32 = -30_000;
+const TONE: f64 = 1_000.0;
+const DS_RATE: f64 = (SAMPLE_RATE / 4) as f64;
+
+// FM carrier at TUNE, modulated by a TONE at `deviation` Hz
+fn signal(deviation: f64, noise: f64) -> Vec<DSPComplex> {
+    let mut rng = Rng(1);
+    (0..Shifter::INPUT_SIZE * BLOCKS)
+        .map(|n| {
+            let t = n as f64 / SAMPLE_RATE as f64;
+            let p = 2.0 * PI * TUNE as f64 * t + deviation / TONE * (2.0 * PI * TONE * t).sin();
+            from_f64(
+                0.4 * p.cos() + noise * rng.next_f64(),
+                0.4 * p.sin() + noise * rng.next_f64(),
+            )
+        })
+        .collect()
+}
+
+fn run(input: &[DSPComplex], channel: u32) -> Vec<f64> {
+    let mut nfm = NfmDemod::new(channel);
+    let mut shifter = Shifter::new();
+    shifter.set_bandwidth(nfm.bandwidth());
+    shifter.set_freq(-TUNE);
+
+    let mut audio = Vec::new();
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        shifter.apply(chunk.try_into().unwrap(), &mut buf);
+        nfm.demod(&mut buf);
+        audio.extend(buf.iter().map(|c| c.re.0 as f64 / common::ONE));
+    }
+    // drop filter transients
+    audio.split_off(audio.len() / 4)
+}
+
+// amplitude and correlation against the modulating tone, fitted in phase
+fn fit_tone(audio: &[f64]) -> (f64, f64) {
+    let (mut c, mut s) = (0.0, 0.0);
+    for (n, a) in audio.iter().enumerate() {
+        let t = 2.0 * PI * TONE * n as f64 / DS_RATE;
+        c += a * t.cos();
+        s += a * t.sin();
+    }
+    let amp = 2.0 * (c * c + s * s).sqrt() / audio.len() as f64;
+    let ph = c.atan2(s);
+    let reference: Vec<f64> = (0..audio.len())
+        .map(|n| (2.0 * PI * TONE * n as f64 / DS_RATE + ph).sin())
+        .collect();
+    (amp, correlation(audio, &reference))
+}
+
+#[test]
+fn peak_deviation_is_half_scale() {
+    for channel in NfmDemod::CHANNELS {
+        let nfm = NfmDemod::new(channel);
+        let audio = run(&signal(nfm.deviation() as f64, 0.0), channel);
+        let (amp, corr) = fit_tone(&audio);
+        assert!(
+            (amp - 0.5).abs() < 0.03,
+            "channel {channel}: amplitude {amp}"
+        );
+        assert!(corr > 0.99, "channel {channel}: correlation {corr}");
+    }
+}
+
+#[test]
+fn output_is_linear_in_deviation() {
+    let channel = NfmDemod::CHANNELS[1];
+    let (a1, _) = fit_tone(&run(&signal(1_000.0, 0.0), channel));
+    let (a2, _) = fit_tone(&run(&signal(4_000.0, 0.0), channel));
+    assert!((a2 / a1 - 4.0).abs() < 0.1, "ratio {}", a2 / a1);
+}
+
+#[test]
+fn independent_of_level() {
+    // scaling of the discriminator should not depend on the signal amplitude
+    let channel = NfmDemod::CHANNELS[0];
+    let strong = signal(2_000.0, 0.0);
+    let weak: Vec<DSPComplex> = strong
+        .iter()
+        .map(|x| DSPComplex::from_i16(x.re.0 / 64, x.im.0 / 64))
+        .collect();
+    let (a1, _) = fit_tone(&run(&strong, channel));
+    let (a2, _) = fit_tone(&run(&weak, channel));
+    assert!((a1 - a2).abs() < 0.03, "strong {a1}, weak {a2}");
+}
+
+#[test]
+fn noisy_signal_keeps_tone() {
+    let channel = NfmDemod::CHANNELS[0];
+    let audio = run(&signal(2_500.0, 0.1), channel);
+    let (amp, corr) = fit_tone(&audio);
+    assert!((amp - 0.5).abs() < 0.05, "amplitude {amp}");
+    assert!(corr > 0.9, "correlation {corr}");
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{correlation, Rng, ONE};
+use fuwasdr_dsp::{
+    dsp::{
+        notch::{AutoNotch, Notch},
+        DSPNum,
+    },
+    sdr::DS_RATE,
+};
+use std::f64::consts::PI;
+
+fn sine(freq: f64, amp: f64, n: usize) -> f64 {
+    amp * (2.0 * PI * freq * n as f64 / DS_RATE as f64).sin()
+}
+
+fn rms(x: &[f64]) -> f64 {
+    (x.iter().map(|v| v * v).sum::<f64>() / x.len() as f64).sqrt()
+}
+
+// gain of a sine through the notch [dB]
+fn notch_gain(notch: &mut Notch, freq: f64) -> f64 {
+    let n = DS_RATE / 2;
+    let out: Vec<f64> = (0..n)
+        .map(|i| {
+            let x = DSPNum((sine(freq, 0.5, i) * ONE).round() as i16);
+            notch.push(x).0 as f64 / ONE
+        })
+        .collect();
+    20.0 * (rms(&out[n / 2..]) / (0.5 / 2f64.sqrt())).log10()
+}
+
+#[test]
+fn notch_response() {
+    for f in [500, 1_000, 2_500] {
+        let mut notch = Notch::new(f, 100, DS_RATE as u32);
+        let g = notch_gain(&mut notch, f as f64);
+        assert!(g < -30.0, "{} Hz: {} dB", f, g);
+        for off in [-300.0, 300.0] {
+            let g = notch_gain(&mut notch, f as f64 + off);
+            assert!(g.abs() < 1.0, "{} Hz {}: {} dB", f, off, g);
+        }
+    }
+}
+
+// a whistle on noise-like audio
+#[test]
+fn auto_notch_removes_tone() {
+    let mut rng = Rng(3);
+    let mut anf: AutoNotch<32> = AutoNotch::new(16, 1 << 10);
+    let n = DS_RATE * 2;
+    // audio alone, and audio with the tone, through the notch
+    let mut audio = Vec::new();
+    let mut out = Vec::new();
+    for i in 0..n {
+        let a = 0.1 * rng.next_f64();
+        let x = DSPNum(((a + sine(1_234.0, 0.3, i)) * ONE).round() as i16);
+        audio.push(a);
+        out.push(anf.push(x).0 as f64 / ONE);
+    }
+    let (audio, out) = (&audio[n / 2..], &out[n / 2..]);
+    // what is left of the tone
+    let w = 2.0 * PI * 1_234.0 / DS_RATE as f64;
+    let (mut c, mut s) = (0.0, 0.0);
+    for (i, o) in out.iter().enumerate() {
+        c += o * (w * i as f64).cos();
+        s += o * (w * i as f64).sin();
+    }
+    let tone = 2.0 * (c * c + s * s).sqrt() / out.len() as f64;
+    let g = 20.0 * (tone / 0.3).log10();
+    assert!(g < -20.0, "tone: {} dB", g);
+    let r = correlation(audio, out);
+    assert!(r > 0.9, "correlation {}", r);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{from_f64, Rng, ONE};
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        nr::{NoiseReduction, NrLevel},
+        shift::Shifter,
+        DS_RATE,
+    },
+};
+use std::f64::consts::PI;
+
+const TONES: [f64; 2] = [700.0, 1_300.0];
+
+// two tones of 0.1 each, and noise of `noise` rms
+fn input(noise: f64, len: usize) -> Vec<DSPComplex> {
+    let mut rng = Rng(11);
+    (0..len)
+        .map(|n| {
+            let t = n as f64 / DS_RATE as f64;
+            let s: f64 = TONES.iter().map(|f| 0.1 * (2.0 * PI * f * t).sin()).sum();
+            // uniform noise of rms 1/sqrt(3)
+            from_f64(s + noise * 3f64.sqrt() * rng.next_f64(), 0.0)
+        })
+        .collect()
+}
+
+fn run(nr: &mut NoiseReduction, input: &[DSPComplex]) -> Vec<f64> {
+    let mut buf = input.to_vec();
+    for chunk in buf.chunks_mut(Shifter::OUTPUT_SIZE) {
+        nr.process(chunk);
+    }
+    buf.iter().map(|c| c.re.0 as f64 / ONE).collect()
+}
+
+// the tones are fitted by least squares, and the rest is noise [dB]
+fn snr(x: &[f64]) -> f64 {
+    let mut signal = vec![0.0; x.len()];
+    for f in TONES {
+        let w = 2.0 * PI * f / DS_RATE as f64;
+        let (mut c, mut s) = (0.0, 0.0);
+        for (n, v) in x.iter().enumerate() {
+            c += v * (w * n as f64).cos();
+            s += v * (w * n as f64).sin();
+        }
+        let (c, s) = (2.0 * c / x.len() as f64, 2.0 * s / x.len() as f64);
+        for (n, v) in signal.iter_mut().enumerate() {
+            *v += c * (w * n as f64).cos() + s * (w * n as f64).sin();
+        }
+    }
+    let ps: f64 = signal.iter().map(|v| v * v).sum();
+    let pn: f64 = x.iter().zip(&signal).map(|(v, s)| (v - s) * (v - s)).sum();
+    10.0 * (ps / pn).log10()
+}
+
+fn snr_at(level: NrLevel) -> f64 {
+    let mut nr = NoiseReduction::new();
+    nr.set_level(level);
+    let out = run(&mut nr, &input(0.05, DS_RATE * 4));
+    // after the filter adapts
+    snr(&out[DS_RATE * 2..])
+}
+
+#[test]
+fn off_passes_through() {
+    let x = input(0.05, DS_RATE / 10);
+    let mut nr = NoiseReduction::new();
+    let out = run(&mut nr, &x);
+    for (a, b) in x.iter().zip(&out) {
+        assert_eq!(a.re.0 as f64 / ONE, *b);
+    }
+}
+
+#[test]
+fn noise_is_reduced() {
+    let off = snr_at(NrLevel::Off);
+    let low = snr_at(NrLevel::Low);
+    let mid = snr_at(NrLevel::Mid);
+    let high = snr_at(NrLevel::High);
+    // about 6dB of it is the 6kHz band of NR
+    assert!(low > off + 10.0, "off {} low {}", off, low);
+    assert!(mid > low + 1.0, "low {} mid {}", low, mid);
+    assert!(high > low + 1.0, "low {} high {}", low, high);
+}
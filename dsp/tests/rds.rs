@@ -0,0 +1,230 @@
+mod common;
+
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        channel::Channel,
+        demod::DemodMethod,
+        rds::{BlockSync, RdsData, RdsDecoder},
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const FS: f64 = SAMPLE_RATE as f64;
+// discriminator output for 75kHz deviation; 1 << 16 is one turn per sample
+const FULL_DEV: f64 = 75_000.0 / FS * 65536.0;
+
+const OFFSETS: [u32; 4] = [0x0fc, 0x198, 0x168, 0x1b4];
+const PI_CODE: u16 = 0x1234;
+
+fn checkword(data: u16) -> u32 {
+    let mut r = (data as u32) << 10;
+    for i in (10..26).rev() {
+        if r & (1 << i) != 0 {
+            r ^= 0x5b9 << (i - 10);
+        }
+    }
+    r
+}
+
+// 26bit blocks of a group
+fn encode(group: [u16; 4]) -> [u32; 4] {
+    core::array::from_fn(|i| ((group[i] as u32) << 10) | (checkword(group[i]) ^ OFFSETS[i]))
+}
+
+fn bits_of(blocks: &[u32]) -> Vec<bool> {
+    blocks
+        .iter()
+        .flat_map(|b| (0..26).rev().map(move |i| b >> i & 1 != 0))
+        .collect()
+}
+
+// groups for PS "FUWA FM ", radio text, and clock time
+fn groups() -> Vec<[u16; 4]> {
+    let ps = b"FUWA FM ";
+    let rt = b"HELLO RDS WORLD ";
+    let mut g = Vec::new();
+    for i in 0..4u16 {
+        let d = (ps[i as usize * 2] as u16) << 8 | ps[i as usize * 2 + 1] as u16;
+        g.push([PI_CODE, i, 0, d]);
+    }
+    for i in 0..4u16 {
+        let c = |k: usize| (rt[k] as u16) << 8 | rt[k + 1] as u16;
+        let a = i as usize * 4;
+        g.push([PI_CODE, 2 << 12 | i, c(a), c(a + 2)]);
+    }
+    // MJD 60000 (2023-02-25), 12:34 UTC, +9h
+    let (mjd, hour, minute, offset) = (60_000u32, 12u16, 34u16, 18u16);
+    g.push([
+        PI_CODE,
+        4 << 12 | (mjd >> 15) as u16,
+        ((mjd << 1) as u16) | (hour >> 4),
+        (hour & 0xf) << 12 | minute << 6 | offset,
+    ]);
+    g
+}
+
+#[test]
+fn block_error_correction() {
+    let blocks: Vec<u32> = groups()
+        .into_iter()
+        .cycle()
+        .take(20)
+        .flat_map(encode)
+        .collect();
+    let mut bits = bits_of(&blocks);
+    // bursts in some blocks after sync
+    for (block, pos, len) in [(10, 3, 1), (13, 20, 3), (22, 0, 5), (31, 8, 4)] {
+        for i in 0..len {
+            let k = block * 26 + pos + i;
+            bits[k] = !bits[k];
+        }
+    }
+
+    let mut sync = BlockSync::new();
+    let mut received = Vec::new();
+    for b in bits {
+        if let Some(g) = sync.push(b) {
+            received.push(g);
+        }
+    }
+    assert!(sync.synced());
+    // synced within the first group; every later block is recovered
+    assert!(received.len() >= 18, "{}", received.len());
+    let expected: Vec<_> = groups().into_iter().cycle().take(20).collect();
+    let tail = &expected[expected.len() - received.len()..];
+    for (r, e) in received.iter().zip(tail).skip(1) {
+        for i in 0..4 {
+            assert_eq!(r[i], Some(e[i]));
+        }
+    }
+}
+
+#[test]
+fn update_only_on_change() {
+    let mut data = RdsData::new();
+    let updates: Vec<bool> = groups().iter().map(|g| data.update(&g.map(Some))).collect();
+    assert!(updates.iter().all(|&u| u));
+    // same groups again change nothing
+    for g in groups() {
+        assert!(!data.update(&g.map(Some)));
+    }
+    // another program service name
+    let mut g = groups()[0];
+    g[3] = u16::from_be_bytes(*b"XY");
+    assert!(data.update(&g.map(Some)));
+    assert_eq!(&data.ps[..2], b"XY");
+}
+
+// MPX with audio, pilot and RDS carrying groups repeatedly
+fn mpx(seconds: f64, rds_level: f64) -> Vec<DSPComplex> {
+    let blocks: Vec<u32> = groups()
+        .into_iter()
+        .cycle()
+        .take(100)
+        .flat_map(encode)
+        .collect();
+    let bits = bits_of(&blocks);
+    // differential coding, then biphase chips
+    let mut e = false;
+    let symbols: Vec<bool> = bits
+        .iter()
+        .map(|&b| {
+            e ^= b;
+            e
+        })
+        .collect();
+
+    let len = (seconds * FS) as usize;
+    (0..len)
+        .map(|n| {
+            let t = n as f64 / FS;
+            let chip = (t * 2_375.0) as usize;
+            let s = if symbols[chip / 2 % symbols.len()] {
+                1.0
+            } else {
+                -1.0
+            };
+            let c = if chip & 1 == 0 { s } else { -s };
+            let v = 0.4 * (2.0 * PI * 1_000.0 * t).sin()
+                + 0.09 * (2.0 * PI * 19_000.0 * t).sin()
+                + rds_level * c * (2.0 * PI * 57_000.0 * t + 0.7).cos();
+            DSPComplex::from_i16((v * FULL_DEV).round() as i16, 0)
+        })
+        .collect()
+}
+
+#[test]
+fn decodes_ps_rt_ct() {
+    let input = mpx(4.0, 0.04);
+    let mut rds = RdsDecoder::new();
+    for chunk in input.chunks_exact(192) {
+        rds.process(chunk);
+    }
+    assert!(rds.synced());
+    let data = rds.data();
+    assert_eq!(data.pi, Some(PI_CODE));
+    assert_eq!(&data.ps, b"FUWA FM ");
+    assert_eq!(&data.radio_text[..16], b"HELLO RDS WORLD ");
+
+    let ct = data.clock.expect("clock time");
+    assert_eq!((ct.hour, ct.minute, ct.offset), (12, 34, 18));
+    assert_eq!(ct.date(), (2023, 2, 25));
+}
+
+#[test]
+fn no_sync_without_rds() {
+    let input = mpx(1.0, 0.0);
+    let mut rds = RdsDecoder::new();
+    for chunk in input.chunks_exact(192) {
+        rds.process(chunk);
+    }
+    assert!(!rds.synced());
+    assert_eq!(rds.data().pi, None);
+}
+
+// FM modulated IQ of the discriminator output
+fn modulate(mpx: &[DSPComplex]) -> Vec<DSPComplex> {
+    let mut phase = 0.0;
+    mpx.iter()
+        .map(|x| {
+            phase += 2.0 * PI * x.re.0 as f64 / 65536.0;
+            common::from_f64(0.5 * phase.cos(), 0.5 * phase.sin())
+        })
+        .collect()
+}
+
+#[test]
+fn channels_keep_own_rds() {
+    let input = modulate(&mpx(6.0, 0.04));
+    let mut channels = [Channel::new(), Channel::new()];
+    for ch in channels.iter_mut() {
+        ch.set_method(DemodMethod::FM);
+    }
+    // published per channel, as core1 does
+    let mut published = [RdsData::new(); 2];
+    let mut out = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    let blocks = input.len() / Shifter::INPUT_SIZE;
+    for (n, chunk) in input.chunks_exact(Shifter::INPUT_SIZE).enumerate() {
+        if n == blocks / 2 {
+            // B is switched to FM again, and starts over
+            channels[1].set_method(DemodMethod::FM);
+        }
+        for (ch, p) in channels.iter_mut().zip(published.iter_mut()) {
+            ch.process(chunk.try_into().unwrap(), &mut out);
+            if let Some(rds) = ch.demods.current().rds() {
+                *p = rds;
+            }
+        }
+        if n == blocks / 2 {
+            assert_eq!(published[1].pi, None);
+        }
+        if n >= blocks / 2 {
+            // A is not disturbed by B starting over
+            assert_eq!(&published[0].ps, b"FUWA FM ");
+        }
+    }
+    assert_eq!(&published[1].ps, b"FUWA FM ");
+}
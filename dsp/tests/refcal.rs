@@ -0,0 +1,184 @@
+mod common;
+
+use common::{from_f64, tone, Rng};
+use fuwasdr_dsp::{
+    dsp::{fft::FFT, window::Window, DSPComplex},
+    sdr::refcal::{
+        accumulate, bins_to_hz, peak_offset, ppb_error, CalFault, CarrierSearch, PhaseTrack,
+    },
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const N: usize = 256;
+
+// Hann spectrum of a tone in noise
+fn spectrum(freq: f64, noise: f64, rng: &mut Rng) -> [DSPComplex; N] {
+    let fft = FFT::<N>::new(Window::Hann);
+    let mut buf = [DSPComplex::zero(); N];
+    for (b, t) in buf.iter_mut().zip(tone(freq, SAMPLE_RATE as f64, 0.5, N)) {
+        let (re, im) = common::to_f64(t);
+        let n = (rng.next_f64() - 0.5) * noise;
+        let m = (rng.next_f64() - 0.5) * noise;
+        *b = from_f64(re + n, im + m);
+    }
+    fft.process(&mut buf);
+    buf
+}
+
+#[test]
+fn peak_is_interpolated() {
+    let mut rng = Rng(1);
+    let bin = SAMPLE_RATE as f64 / N as f64;
+    for &f in &[10_000.0, 10_100.0, 10_375.0, 10_600.0, -5_210.0, 123.0] {
+        let mut mag = [0; N];
+        accumulate(&spectrum(f, 0.0, &mut rng), &mut mag);
+        let center = N as i32 / 2 + (f / bin).round() as i32;
+        let hz = bins_to_hz(peak_offset(&mag, center, 4).unwrap(), N);
+        // within 1% of a bin
+        assert!((hz as f64 - f).abs() < bin / 100.0, "{f}: {hz}");
+    }
+}
+
+#[test]
+fn averaging_in_noise() {
+    let mut rng = Rng(2);
+    let f = 15_321.0;
+    let mut search = CarrierSearch::<N>::new(15_000, 2, 16);
+    let mut n = 0;
+    loop {
+        // a different carrier while settling
+        let buf = spectrum(if n < 2 { 30_000.0 } else { f }, 0.5, &mut rng);
+        n += 1;
+        if search.push(&buf) {
+            break;
+        }
+    }
+    assert_eq!(n, 18);
+    let hz = search.carrier().unwrap();
+    assert!((hz as f64 - f).abs() < 20.0, "{hz}");
+}
+
+#[test]
+fn error_sign() {
+    // 10MHz carrier expected at +1kHz; 1ppm fast reference puts the LO 10Hz high
+    assert_eq!(ppb_error(990_000, 1_000_000, 10_000_000), 1000);
+    assert_eq!(ppb_error(1_010_000, 1_000_000, 10_000_000), -1000);
+    assert_eq!(ppb_error(1_000_000, 1_000_000, 10_000_000), 0);
+    // 1ppb is 10mHz
+    assert_eq!(ppb_error(999_990, 1_000_000, 10_000_000), 1);
+    // nothing to find
+    assert_eq!(peak_offset(&[0; N], N as i32 / 2, 4), None);
+}
+
+const BLOCK: usize = 384;
+
+// carrier of amp at freq [Hz] in noise, continuous over len samples
+fn carrier(freq: f64, amp: f64, noise: f64, len: usize, rng: &mut Rng) -> Vec<DSPComplex> {
+    (0..len)
+        .map(|n| {
+            let p = 2.0 * PI * freq * n as f64 / SAMPLE_RATE as f64;
+            let (a, b) = (
+                (rng.next_f64() - 0.5) * noise,
+                (rng.next_f64() - 0.5) * noise,
+            );
+            from_f64(amp * p.cos() + a, amp * p.sin() + b)
+        })
+        .collect()
+}
+
+// blocks of input as they come to the tracker: some skipped, some partly overwritten
+fn track(input: &[DSPComplex], track: &mut PhaseTrack) {
+    for (k, block) in input.chunks_exact(BLOCK).enumerate() {
+        if k % 7 == 3 {
+            continue;
+        }
+        let from = 64 * (k % 3);
+        if track.push((k * BLOCK + from) as u32, &block[from..]) {
+            return;
+        }
+    }
+    panic!("not enough blocks");
+}
+
+#[test]
+fn phase_resolves_mhz() {
+    let mut rng = Rng(3);
+    for (f, start) in [
+        (9_975.025, 9_990_000),
+        (-20_000.333, -20_020_000),
+        (15_000.0, 15_000_000),
+    ] {
+        let input = carrier(f, 0.1, 0.5, BLOCK * 1500, &mut rng);
+        let mut t = PhaseTrack::new(start, 1200);
+        track(&input, &mut t);
+        let mhz = t.carrier().unwrap();
+        // 1ppb of 10MHz is 10mHz
+        assert!((mhz as f64 - f * 1000.0).abs() <= 5.0, "{f}: {mhz}");
+    }
+}
+
+#[test]
+fn ppb_of_standard() {
+    // 10MHz, 10kHz above the LO; the reference is 2500ppb fast
+    let (lo, offset) = (9_990_000u32, 10_000_000i64);
+    let f = (offset as f64 - lo as f64 * 2.5e-3) / 1000.0;
+    let mut rng = Rng(4);
+    let input = carrier(f, 0.05, 0.5, BLOCK * 1500, &mut rng);
+
+    let mut s = CarrierSearch::<N>::new((offset / 1000) as i32, 0, 16);
+    search(&input, &mut s);
+    let coarse = s.carrier().unwrap();
+    let mut t = PhaseTrack::new(coarse as i64 * 1000, 1200);
+    track(&input, &mut t);
+    let err = ppb_error(t.carrier().unwrap(), offset, lo);
+    assert!((err - 2500).abs() <= 1, "{err}");
+}
+
+// spectra of input, until the search is done
+fn search(input: &[DSPComplex], search: &mut CarrierSearch<N>) {
+    let fft = FFT::<N>::new(Window::Hann);
+    for block in input.chunks_exact(BLOCK) {
+        let mut buf: [DSPComplex; N] = block[..N].try_into().unwrap();
+        fft.process(&mut buf);
+        if search.push(&buf) {
+            return;
+        }
+    }
+    panic!("not enough blocks");
+}
+
+#[test]
+fn weak_carrier_is_rejected() {
+    let mut rng = Rng(5);
+    // nothing but noise, and a carrier hardly over it
+    for amp in [0.0, 0.01] {
+        let input = carrier(10_000.0, amp, 0.5, BLOCK * 16, &mut rng);
+        let mut s = CarrierSearch::<N>::new(10_000, 0, 16);
+        search(&input, &mut s);
+        assert_eq!(s.carrier(), Err(CalFault::Weak), "{amp}");
+    }
+}
+
+#[test]
+fn moving_carrier_is_rejected() {
+    let mut rng = Rng(6);
+    let mut s = CarrierSearch::<N>::new(10_000, 0, 16);
+    for n in 0.. {
+        // 3 bins apart
+        let f = [7_750.0, 10_000.0, 12_250.0][n % 3];
+        if s.push(&spectrum(f, 0.1, &mut rng)) {
+            break;
+        }
+    }
+    assert_eq!(s.carrier(), Err(CalFault::Unstable));
+}
+
+#[test]
+fn phase_in_noise_is_rejected() {
+    let mut rng = Rng(7);
+    let input = carrier(10_000.0, 0.005, 0.5, BLOCK * 1500, &mut rng);
+    let mut t = PhaseTrack::new(10_000_000, 1200);
+    track(&input, &mut t);
+    assert_eq!(t.carrier(), Err(CalFault::Unstable));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::{from_f64, Rng};
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        demod::{Demodulator, SamDemod, SamSideband},
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const BLOCKS: usize = 400;
+const TUNE: i32 = 25_000;
+const DS_RATE: f64 = (SAMPLE_RATE / 4) as f64;
+
+// carrier at TUNE + offset, modulated by `upper` / `lower` sideband tones at `tone` Hz
+fn signal(offset: f64, tone: f64, upper: f64, lower: f64, noise: f64) -> Vec<DSPComplex> {
+    let mut rng = Rng(1);
+    (0..Shifter::INPUT_SIZE * BLOCKS)
+        .map(|n| {
+            let t = 2.0 * PI * n as f64 / SAMPLE_RATE as f64;
+            let c = (TUNE as f64 + offset) * t;
+            let m = tone * t;
+            let re = 0.3 * c.cos() + upper * (c + m).cos() + lower * (c - m).cos();
+            let im = 0.3 * c.sin() + upper * (c + m).sin() + lower * (c - m).sin();
+            from_f64(re + noise * rng.next_f64(), im + noise * rng.next_f64())
+        })
+        .collect()
+}
+
+// audio and lock state after the signal
+fn run(input: &[DSPComplex], sideband: SamSideband) -> (Vec<f64>, SamDemod) {
+    let mut sam = SamDemod::new(sideband);
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-TUNE);
+
+    let mut audio = Vec::new();
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        shifter.apply(chunk.try_into().unwrap(), &mut buf);
+        sam.demod(&mut buf);
+        audio.extend(buf.iter().map(|c| c.re.0 as f64 / common::ONE));
+    }
+    // drop acquisition
+    (audio.split_off(audio.len() / 2), sam)
+}
+
+fn rms(audio: &[f64]) -> f64 {
+    (audio.iter().map(|a| a * a).sum::<f64>() / audio.len() as f64).sqrt()
+}
+
+#[test]
+fn locks_to_offset_carrier() {
+    for offset in [-200.0, 0.0, 35.0, 200.0] {
+        let (_, sam) = run(&signal(offset, 1_000.0, 0.1, 0.1, 0.0), SamSideband::Double);
+        assert!(sam.locked(), "{} Hz", offset);
+        let f = sam.carrier_offset() as f64;
+        assert!((f - offset).abs() < 2.0, "{} Hz: {}", offset, f);
+    }
+}
+
+#[test]
+fn no_lock_on_noise() {
+    let input: Vec<_> = {
+        let mut rng = Rng(7);
+        (0..Shifter::INPUT_SIZE * BLOCKS)
+            .map(|_| from_f64(0.3 * rng.next_f64(), 0.3 * rng.next_f64()))
+            .collect()
+    };
+    let (_, sam) = run(&input, SamSideband::Double);
+    assert!(!sam.locked());
+}
+
+#[test]
+fn recovers_modulation() {
+    let (audio, _) = run(&signal(50.0, 1_000.0, 0.1, 0.1, 0.05), SamSideband::Double);
+    let reference: Vec<f64> = (0..audio.len())
+        .map(|n| (2.0 * PI * 1_000.0 * n as f64 / DS_RATE).cos())
+        .collect();
+    // phase of the tone is unknown; check the best over one period
+    let best = (0..48)
+        .map(|d| common::correlation(&audio[d..], &reference[..audio.len() - d]))
+        .fold(f64::MIN, f64::max);
+    assert!(best > 0.95, "{}", best);
+    // both sidebands: 0.2 amplitude
+    let level = rms(&audio) * 2f64.sqrt();
+    assert!((level - 0.2).abs() < 0.02, "{}", level);
+}
+
+#[test]
+fn sideband_selection() {
+    // only the upper sideband is modulated
+    let input = signal(0.0, 1_500.0, 0.1, 0.0, 0.0);
+    let dsb = rms(&run(&input, SamSideband::Double).0);
+    let usb = rms(&run(&input, SamSideband::Upper).0);
+    let lsb = rms(&run(&input, SamSideband::Lower).0);
+    assert!(
+        (20.0 * (usb / dsb).log10() - 6.0).abs() < 1.0,
+        "{} {}",
+        usb,
+        dsb
+    );
+    assert!(20.0 * (lsb / usb).log10() < -30.0, "{} {}", lsb, usb);
+}
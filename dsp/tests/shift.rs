@@ -0,0 +1,143 @@
+mod common;
+
+use common::{measure_freq, to_f64};
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{shift::Shifter, DS_RATE},
+    SAMPLE_RATE,
+};
+
+const BLOCKS: usize = 20;
+// outputs to drop while filters settle
+const SETTLE: usize = Shifter::OUTPUT_SIZE * 2;
+
+// run shifter over consecutive blocks of input
+fn run(shifter: &mut Shifter, input: &[DSPComplex]) -> Vec<DSPComplex> {
+    let mut out = Vec::new();
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        shifter.apply(chunk.try_into().unwrap(), &mut buf);
+        out.extend_from_slice(&buf);
+    }
+    out
+}
+
+fn rms(buf: &[DSPComplex]) -> f64 {
+    let p = buf
+        .iter()
+        .map(|&c| {
+            let (re, im) = to_f64(c);
+            re * re + im * im
+        })
+        .sum::<f64>();
+    (p / buf.len() as f64).sqrt()
+}
+
+// output level [dB] of a tone at f_in, with shifter tuned to tune
+fn response_db(f_in: i32, tune: i32) -> f64 {
+    let input = common::tone(
+        f_in as f64,
+        SAMPLE_RATE as f64,
+        0.5,
+        Shifter::INPUT_SIZE * BLOCKS,
+    );
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-tune);
+    let out = run(&mut shifter, &input);
+    20.0 * (rms(&out[SETTLE..]) / 0.5).log10()
+}
+
+#[test]
+fn shift_frequency_accuracy() {
+    // (input tone, demod tune)
+    let cases = [
+        (31_000, 30_000),
+        (-45_500, -46_000),
+        (12_345, 12_000),
+        (80_000, 79_250),
+        (-2_000, 0),
+    ];
+    for (f_in, tune) in cases {
+        let input = common::tone(
+            f_in as f64,
+            SAMPLE_RATE as f64,
+            0.5,
+            Shifter::INPUT_SIZE * BLOCKS,
+        );
+        let mut shifter = Shifter::new();
+        shifter.set_freq(-tune);
+        let out = run(&mut shifter, &input);
+
+        let f = measure_freq(&out[SETTLE..], DS_RATE as f64);
+        let expected = (f_in - tune) as f64;
+        // omega is quantized to 2^18 steps per sample rate (~0.73Hz)
+        assert!(
+            (f - expected).abs() < 1.0,
+            "{} - {}: got {} Hz",
+            f_in,
+            tune,
+            f
+        );
+    }
+}
+
+#[test]
+fn shift_keeps_amplitude() {
+    let input = common::tone(
+        20_500.0,
+        SAMPLE_RATE as f64,
+        0.5,
+        Shifter::INPUT_SIZE * BLOCKS,
+    );
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-20_000);
+    let out = run(&mut shifter, &input);
+
+    for &c in &out[SETTLE..] {
+        let (re, im) = to_f64(c);
+        let a = (re * re + im * im).sqrt();
+        assert!((a - 0.5).abs() < 0.01, "amplitude {}", a);
+    }
+}
+
+#[test]
+fn shift_rejects_aliases() {
+    // signals 48kHz away used to fold straight onto the channel
+    for offset in [48_000, -48_000, 96_000, 47_000] {
+        let db = response_db(10_000 + offset, 10_000);
+        assert!(db < -60.0, "offset {}: {} dB", offset, db);
+    }
+}
+
+#[test]
+fn shift_channel_filter() {
+    // default bandwidth is 10kHz (+-5kHz)
+    for f in [0, 1_000, -3_000] {
+        let db = response_db(f + 30_000, 30_000);
+        assert!(db.abs() < 1.0, "passband {}: {} dB", f, db);
+    }
+    // adjacent stations 9-10kHz away
+    for f in [9_000, -9_000, 10_000, -10_000, 20_000] {
+        let db = response_db(f + 30_000, 30_000);
+        assert!(db < -50.0, "stopband {}: {} dB", f, db);
+    }
+}
+
+#[test]
+fn shift_bandwidth() {
+    let input = common::tone(
+        36_000.0,
+        SAMPLE_RATE as f64,
+        0.5,
+        Shifter::INPUT_SIZE * BLOCKS,
+    );
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-30_000);
+    shifter.set_bandwidth(3_000);
+    let out = run(&mut shifter, &input);
+    assert!(rms(&out[SETTLE..]) < 0.5 * 0.01);
+
+    shifter.set_bandwidth(20_000);
+    let out = run(&mut shifter, &input);
+    assert!((rms(&out[SETTLE..]) - 0.5).abs() < 0.02);
+}
@@ -0,0 +1,53 @@
+use fuwasdr_dsp::sdr::smeter::{dbm, s9_level, s_unit, SMeter, CALIBRATION};
+
+#[test]
+fn pga_gain_is_taken_away() {
+    let f = 7_000_000;
+    let (_, full_scale) = CALIBRATION[1];
+    assert_eq!(dbm(-50 << 8, 0, f), (full_scale - 50) << 8);
+    // 20dB of PGA gain: the same antenna level shows 20dB higher in dBFS
+    assert_eq!(dbm(-30 << 8, 40, f), dbm(-50 << 8, 0, f));
+}
+
+#[test]
+fn band_table() {
+    for (upper, full_scale) in CALIBRATION.iter().take(CALIBRATION.len() - 1) {
+        assert_eq!(dbm(0, 0, upper - 1), full_scale << 8);
+    }
+}
+
+#[test]
+fn s_units() {
+    let s9 = s9_level(14_000_000);
+    assert_eq!(s9, -73);
+    assert_eq!(s9_level(145_000_000), -93);
+    assert_eq!(s_unit(-73, s9), (9, 0));
+    assert_eq!(s_unit(-53, s9), (9, 20));
+    assert_eq!(s_unit(-74, s9), (8, 0));
+    assert_eq!(s_unit(-79, s9), (8, 0));
+    assert_eq!(s_unit(-80, s9), (7, 0));
+    assert_eq!(s_unit(-127, s9), (0, 0));
+    assert_eq!(s_unit(-200, s9), (0, 0));
+}
+
+#[test]
+fn peak_hold() {
+    let mut m = SMeter::new();
+    m.update(-60 << 8);
+    m.update(-90 << 8);
+    assert_eq!(m.level(), -90 << 8);
+    assert_eq!(m.peak(), -60 << 8);
+    for _ in 1..SMeter::HOLD {
+        m.update(-90 << 8);
+    }
+    assert_eq!(m.peak(), -60 << 8);
+    m.update(-90 << 8);
+    assert_eq!(m.peak(), (-60 << 8) - SMeter::DECAY);
+    // down to the level at most
+    for _ in 0..100 {
+        m.update(-90 << 8);
+    }
+    assert_eq!(m.peak(), -90 << 8);
+    m.update(-40 << 8);
+    assert_eq!(m.peak(), -40 << 8);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{from_f64, Rng};
+use fuwasdr_dsp::{
+    dsp::{db10, DSPComplex},
+    sdr::{
+        demod::{Demodulator, NfmDemod},
+        shift::Shifter,
+        squelch::Squelch,
+    },
+};
+
+const BLOCKS: usize = 100;
+
+#[test]
+fn db10_accuracy() {
+    let mut max_err: f64 = 0.0;
+    for x in (1..100_000u64).chain((0..60).map(|k| 3u64 << k)) {
+        let err = db10(x) as f64 / 256.0 - 10.0 * (x as f64).log10();
+        max_err = max_err.max(err.abs());
+    }
+    assert!(max_err < 0.15, "max error {max_err} dB");
+}
+
+// constant carrier of `amp` at DS_RATE; returns the squelch after it
+fn carrier(squelch: &mut Squelch, amp: f64) -> Vec<f64> {
+    let mut out = Vec::new();
+    for _ in 0..BLOCKS {
+        let mut buf = [from_f64(amp, 0.0); Shifter::OUTPUT_SIZE];
+        squelch.measure(&buf);
+        squelch.apply(&mut buf, false);
+        out.extend(buf.iter().map(|x| x.re.0 as f64 / amp / common::ONE));
+    }
+    out
+}
+
+#[test]
+fn carrier_level() {
+    let mut sq = Squelch::new();
+    sq.set_carrier_level(Some(-40));
+
+    // -46dBFS
+    carrier(&mut sq, 0.005);
+    assert!(!sq.is_open());
+    // -34dBFS
+    carrier(&mut sq, 0.02);
+    assert!(sq.is_open());
+    // within hysteresis
+    carrier(&mut sq, 0.0085);
+    assert!(sq.is_open());
+    carrier(&mut sq, 0.005);
+    assert!(!sq.is_open());
+
+    sq.set_carrier_level(None);
+    carrier(&mut sq, 0.0);
+    assert!(sq.is_open());
+}
+
+#[test]
+fn mutes_smoothly() {
+    let mut sq = Squelch::new();
+    sq.set_carrier_level(Some(-20));
+    carrier(&mut sq, 0.5);
+    let g = carrier(&mut sq, 0.05);
+    assert!(g.last().unwrap().abs() < 1e-3);
+    // no step in gain; 10ms ramp
+    let max_step = g
+        .windows(2)
+        .map(|w| (w[1] - w[0]).abs())
+        .fold(0.0, f64::max);
+    assert!(max_step < 0.01, "step {max_step}");
+    let ramp = g.iter().filter(|&&x| x > 0.01 && x < 0.99).count();
+    assert!((400..560).contains(&ramp), "ramp {ramp} samples");
+}
+
+// NFM discriminator on a carrier with noise, after Shifter
+fn noise_squelch(carrier: f64, noise: f64) -> Squelch {
+    let mut rng = Rng(3);
+    let mut nfm = NfmDemod::new(NfmDemod::CHANNELS[0]);
+    let mut shifter = Shifter::new();
+    shifter.set_bandwidth(nfm.bandwidth());
+    shifter.set_freq(0);
+    let mut sq = Squelch::new();
+    sq.set_noise_level(Some(20));
+
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for _ in 0..BLOCKS {
+        let input: Vec<DSPComplex> = (0..Shifter::INPUT_SIZE)
+            .map(|_| from_f64(carrier + noise * rng.next_f64(), noise * rng.next_f64()))
+            .collect();
+        shifter.apply(input.as_slice().try_into().unwrap(), &mut buf);
+        nfm.demod(&mut buf);
+        sq.measure_quieting(nfm.quieting().unwrap());
+        sq.apply(&mut buf, true);
+    }
+    sq
+}
+
+#[test]
+fn fm_noise_squelch() {
+    assert!(!noise_squelch(0.0, 0.1).is_open());
+    assert!(!noise_squelch(0.003, 0.01).is_open());
+    assert!(noise_squelch(0.03, 0.01).is_open());
+    // level does not matter
+    assert!(noise_squelch(0.3, 0.1).is_open());
+    // digital silence is not a signal
+    assert!(!noise_squelch(0.0, 0.0).is_open());
+}
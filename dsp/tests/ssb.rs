@@ -0,0 +1,75 @@
+mod common;
+
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::{
+        demod::{Demodulator, Sideband, SsbDemod},
+        shift::Shifter,
+    },
+    SAMPLE_RATE,
+};
+
+const BLOCKS: usize = 40;
+const TUNE: i32 = 25_000;
+
+// audio rms of a tone at TUNE + offset
+fn audio_rms(offset: i32, sideband: Sideband, bandwidth: u32) -> f64 {
+    let input = common::tone(
+        (TUNE + offset) as f64,
+        SAMPLE_RATE as f64,
+        0.5,
+        Shifter::INPUT_SIZE * BLOCKS,
+    );
+    let mut ssb = SsbDemod::new(sideband);
+    ssb.set_bandwidth(bandwidth);
+    let mut shifter = Shifter::new();
+    shifter.set_freq(-(TUNE + ssb.center_offset()));
+
+    let mut audio = Vec::new();
+    let mut buf = [DSPComplex::zero(); Shifter::OUTPUT_SIZE];
+    for chunk in input.chunks_exact(Shifter::INPUT_SIZE) {
+        shifter.apply(chunk.try_into().unwrap(), &mut buf);
+        ssb.demod(&mut buf);
+        audio.extend(buf.iter().map(|c| c.re.0 as f64 / common::ONE));
+    }
+    // drop filter transients
+    let audio = &audio[audio.len() / 4..];
+    (audio.iter().map(|a| a * a).sum::<f64>() / audio.len() as f64).sqrt()
+}
+
+fn db(rms: f64) -> f64 {
+    // full level is a 0.5 amplitude sinusoid
+    20.0 * (rms / (0.5 / 2f64.sqrt())).log10()
+}
+
+#[test]
+fn usb_passes_upper_sideband() {
+    for f in [500, 1_000, 2_000] {
+        let d = db(audio_rms(f, Sideband::Upper, 2_400));
+        assert!(d.abs() < 1.5, "{} Hz: {} dB", f, d);
+    }
+    for f in [-500, -1_000, -2_000] {
+        let d = db(audio_rms(f, Sideband::Upper, 2_400));
+        assert!(d < -40.0, "{} Hz: {} dB", f, d);
+    }
+}
+
+#[test]
+fn lsb_passes_lower_sideband() {
+    for f in [-500, -1_000, -2_000] {
+        let d = db(audio_rms(f, Sideband::Lower, 2_400));
+        assert!(d.abs() < 1.5, "{} Hz: {} dB", f, d);
+    }
+    for f in [500, 1_000, 2_000] {
+        let d = db(audio_rms(f, Sideband::Lower, 2_400));
+        assert!(d < -40.0, "{} Hz: {} dB", f, d);
+    }
+}
+
+#[test]
+fn ssb_bandwidth() {
+    let wide = db(audio_rms(2_700, Sideband::Upper, 3_000));
+    let narrow = db(audio_rms(2_700, Sideband::Upper, 1_800));
+    assert!(wide.abs() < 1.5, "{} dB", wide);
+    assert!(narrow < -30.0, "{} dB", narrow);
+}
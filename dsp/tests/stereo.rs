@@ -0,0 +1,149 @@
+mod common;
+
+use fuwasdr_dsp::{
+    dsp::DSPComplex,
+    sdr::demod::{Deemphasis, StereoDecoder},
+    SAMPLE_RATE,
+};
+use std::f64::consts::PI;
+
+const FS: f64 = SAMPLE_RATE as f64;
+const DS_RATE: f64 = FS / 4.0;
+// discriminator output for 75kHz deviation; 1 << 16 is one turn per sample
+const FULL_DEV: f64 = 75_000.0 / FS * 65536.0;
+
+// MPX with 1kHz on L, 2.5kHz on R, 90% audio and 10% pilot
+fn mpx(len: usize, pilot: bool) -> Vec<DSPComplex> {
+    (0..len)
+        .map(|n| {
+            let t = 2.0 * PI * n as f64 / FS;
+            let l = (1_000.0 * t).sin();
+            let r = (2_500.0 * t).sin();
+            let p = if pilot { 0.1 } else { 0.0 };
+            let v = 0.45 * (l + r) / 2.0
+                + 0.45 * (l - r) / 2.0 * (2.0 * 19_000.0 * t).sin()
+                + p * (19_000.0 * t).sin();
+            DSPComplex::from_i16((v * FULL_DEV).round() as i16, 0)
+        })
+        .collect()
+}
+
+// (L, R) of the latter half
+fn decode(input: &[DSPComplex]) -> (Vec<f64>, Vec<f64>, StereoDecoder) {
+    let mut dec = StereoDecoder::new();
+    let (mut l, mut r) = (Vec::new(), Vec::new());
+    let mut out = [DSPComplex::zero(); 48];
+    for chunk in input.chunks_exact(192) {
+        let k = dec.process(chunk, &mut out);
+        assert_eq!(k, 48);
+        l.extend(out.iter().map(|c| c.re.0 as f64 / FULL_DEV));
+        r.extend(out.iter().map(|c| c.im.0 as f64 / FULL_DEV));
+    }
+    let h = l.len() / 2;
+    (l.split_off(h), r.split_off(h), dec)
+}
+
+// amplitude of a tone in real samples
+fn amplitude(x: &[f64], f: f64, fs: f64) -> f64 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for (n, v) in x.iter().enumerate() {
+        let t = 2.0 * PI * f * n as f64 / fs;
+        re += v * t.cos();
+        im += v * t.sin();
+    }
+    2.0 * (re * re + im * im).sqrt() / x.len() as f64
+}
+
+// de-emphasis response of 50us
+fn deemph(f: f64) -> f64 {
+    1.0 / (1.0 + (2.0 * PI * f * 50e-6).powi(2)).sqrt()
+}
+
+#[test]
+fn stereo_separation() {
+    let (l, r, dec) = decode(&mpx(192 * 500, true));
+    assert!(dec.is_stereo());
+
+    let l1 = amplitude(&l, 1_000.0, DS_RATE);
+    let r1 = amplitude(&r, 1_000.0, DS_RATE);
+    let l2 = amplitude(&l, 2_500.0, DS_RATE);
+    let r2 = amplitude(&r, 2_500.0, DS_RATE);
+    assert!((l1 / (0.45 * deemph(1_000.0)) - 1.0).abs() < 0.1, "{}", l1);
+    assert!((r2 / (0.45 * deemph(2_500.0)) - 1.0).abs() < 0.1, "{}", r2);
+    assert!(20.0 * (r1 / l1).log10() < -25.0, "L to R: {} {}", r1, l1);
+    assert!(20.0 * (l2 / r2).log10() < -25.0, "R to L: {} {}", l2, r2);
+}
+
+#[test]
+fn mono_without_pilot() {
+    let (l, r, dec) = decode(&mpx(192 * 500, false));
+    assert!(!dec.is_stereo());
+    assert_eq!(l, r);
+    let l1 = amplitude(&l, 1_000.0, DS_RATE);
+    assert!((l1 / (0.225 * deemph(1_000.0)) - 1.0).abs() < 0.1, "{}", l1);
+}
+
+#[test]
+fn deemphasis_response() {
+    for (tau, f) in [(50, 1_000.0), (50, 5_000.0), (75, 2_000.0), (75, 10_000.0)] {
+        let mut d = Deemphasis::new(tau);
+        let y: Vec<f64> = (0..4_800)
+            .map(|n| {
+                let x = 0.5 * (2.0 * PI * f * n as f64 / DS_RATE).sin();
+                d.push((x * common::ONE) as i32).0 as f64 / common::ONE
+            })
+            .skip(480)
+            .collect();
+        // one pole lowpass with the pole at exp(-1 / (fs tau))
+        let p = (-1.0 / (DS_RATE * tau as f64 * 1e-6)).exp();
+        let w = 2.0 * PI * f / DS_RATE;
+        let expected = 0.5 * (1.0 - p) / (1.0 - 2.0 * p * w.cos() + p * p).sqrt();
+        let a = amplitude(&y, f, DS_RATE);
+        let err = 20.0 * (a / expected).log10();
+        assert!(err.abs() < 0.5, "{}us {}Hz: {} dB", tau, f, err);
+    }
+}
+
+// subcarrier content at f, with or without pilot
+fn subcarrier(len: usize, f: f64, pilot: bool) -> Vec<DSPComplex> {
+    (0..len)
+        .map(|n| {
+            let t = 2.0 * PI * n as f64 / FS;
+            let p = if pilot { 0.1 } else { 0.0 };
+            let v = 0.2 * (f * t).sin() + p * (19_000.0 * t).sin();
+            DSPComplex::from_i16((v * FULL_DEV).round() as i16, 0)
+        })
+        .collect()
+}
+
+#[test]
+fn subcarriers_rejected() {
+    // 38kHz DSB from 33kHz up, and 57kHz RDS, would fold into the audio at DS_RATE
+    for (f, pilot) in [
+        (33_000.0, false),
+        (38_000.0, false),
+        (57_000.0, false),
+        (57_000.0, true),
+    ] {
+        let (l, r, _) = decode(&subcarrier(192 * 400, f, pilot));
+        let m = f % DS_RATE;
+        let alias = m.min(DS_RATE - m);
+        for x in [&l, &r] {
+            let db = 20.0 * (amplitude(x, alias, DS_RATE) / 0.2).log10();
+            assert!(db < -70.0, "{}Hz at {}Hz: {} dB", f, alias, db);
+        }
+    }
+}
+
+#[test]
+fn audio_passband() {
+    // flat up to 15kHz, but for de-emphasis, one-pole at DS_RATE
+    let k = (-1.0 / (DS_RATE * 50e-6)).exp();
+    for f in [5_000.0, 10_000.0, 15_000.0] {
+        let (l, _, _) = decode(&subcarrier(192 * 400, f, false));
+        let w = 2.0 * PI * f / DS_RATE;
+        let h = (1.0 - k) / (1.0 - 2.0 * k * w.cos() + k * k).sqrt();
+        let a = amplitude(&l, f, DS_RATE) / (0.2 * h);
+        assert!((20.0 * a.log10()).abs() < 1.0, "{}Hz: {}", f, a);
+    }
+}
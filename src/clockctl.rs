@@ -5,10 +5,37 @@ use rp2040_hal::fugit::HertzU32;
 
 const XTAL_FREQ: HertzU32 = HertzU32::MHz(25_u32);
 
+// PLL (VCO) range
+const MIN_VCO: u64 = 600_000_000;
+const MAX_VCO: u64 = 900_000_000;
+// even integer MultiSynth dividers only; phase offset and low jitter need them
+const MIN_MS_DIV: u32 = 4;
+const MAX_MS_DIV: u32 = 1800;
+// PHOFF is 7 bits; above, the phase is made by the fine frequency difference
+const MAX_PHOFF_DIV: u32 = 127;
+// largest fractional denominator
+const MAX_DENOM: u32 = 1_048_575;
+// lowest output of MultiSynth, with the largest divider and PLL
+const MIN_MS_FREQ: u32 = 333_334;
+const MAX_MS_FREQ: u32 = 225_000_000;
+// R divider goes up to 128; a bit above MIN_MS_FREQ / 128
+const MIN_FREQ: u32 = 8_000;
+// 5th harmonic, up to 9 digits
+const MAX_FREQ: u32 = 999_999_999;
+const MAX_R_DIV_LOG2: u8 = 7;
+// LO harmonics the mixer is used on; above MAX_MS_FREQ, the lowest that fits
+const HARMONICS: [u32; 3] = [1, 3, 5];
+
+// reference correction range [ppb]
+pub const MAX_PPB: i32 = 100_000;
+
 pub struct ClockCtl<Alarm: rp2040_hal::timer::Alarm> {
     alarm: Alarm,
+    xtal: HertzU32,
+    // reference error [ppb]; positive is fast
+    ppb: i32,
     current_freq: HertzU32,
-    current_div_idx: usize,
+    plan: Plan,
 }
 
 pub enum Error {
@@ -34,8 +61,10 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
     pub fn new(alarm: Alarm) -> Self {
         Self {
             alarm,
+            xtal: XTAL_FREQ,
+            ppb: 0,
             current_freq: HertzU32::MHz(0),
-            current_div_idx: 0,
+            plan: Plan::default(),
         }
     }
 
@@ -72,36 +101,62 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
         self.current_freq
     }
 
-    pub fn get_tune_step(&self) -> u8 {
-        self.get_tune_factors().ts
+    // what the output actually is [mHz]
+    pub fn get_achieved_freq_mhz(&self) -> u64 {
+        self.plan.output_mhz(self.xtal_mhz())
     }
 
-    fn get_tune_factors(&self) -> &TuneFactors {
-        &TUNE_FACTORS[self.current_div_idx]
+    // LO harmonic the mixer works on; 1 is the fundamental
+    pub fn harmonic(&self) -> u8 {
+        self.plan.harmonic as u8
     }
 
-    pub fn tune(&mut self, target: HertzU32) -> Result<(), Error> {
-        let a = self.get_tune_factors().get_synth_param(target);
-        if a != 0 {
-            // just set pll
-            self.set_plla_mul(a, self.get_tune_factors().c)?;
-        } else {
-            // change div
-            self.current_div_idx = find_div_idx(target).ok_or(Error::InvalidValue)?;
+    // on the 3rd, the spectrum comes inverted; see IqCorrection
+    pub fn iq_swapped(&self) -> bool {
+        self.plan.harmonic % 4 == 3
+    }
+
+    pub fn ppb(&self) -> i32 {
+        self.ppb
+    }
+
+    // takes effect on the current frequency at once; returns the achieved one [mHz]
+    pub fn set_ppb(&mut self, ppb: i32) -> Result<u64, Error> {
+        self.ppb = ppb.clamp(-MAX_PPB, MAX_PPB);
+        if self.current_freq.to_Hz() == 0 {
+            return Ok(0);
+        }
+        self.tune(self.current_freq)
+    }
+
+    // actual reference [mHz]
+    fn xtal_mhz(&self) -> u64 {
+        let x = self.xtal.to_Hz() as i64 * 1000;
+        (x + x * self.ppb as i64 / 1_000_000_000) as u64
+    }
+
+    // returns the achieved frequency [mHz], which is a little off the target
+    pub fn tune(&mut self, target: HertzU32) -> Result<u64, Error> {
+        let plan =
+            Plan::new(target.to_Hz(), self.xtal_mhz(), &self.plan).ok_or(Error::InvalidValue)?;
+        let change_div = plan.div != self.plan.div || plan.r != self.plan.r;
+        self.plan = plan;
+        // the fine phase difference is made at the final PLL frequency
+        self.set_plla_mul()?;
+        if change_div {
             self.set_div()?;
-            let a = self.get_tune_factors().get_synth_param(target);
-            self.set_plla_mul(a, self.get_tune_factors().c)?;
         }
 
         self.current_freq = target;
-        Ok(())
+        Ok(self.get_achieved_freq_mhz())
     }
 
     fn set_div(&mut self) -> Result<(), Error> {
-        let div = self.get_tune_factors().div;
-        defmt::info!("setting div {}", div);
-        if div <= 127 {
-            let p1 = div - 4;
+        let Plan { div, r, .. } = self.plan;
+        defmt::info!("setting div {} r {}", div, 1 << r);
+        let ms = synth_params(div, 0, 1);
+        // R divider is only used below MIN_MS_FREQ, where div is large
+        if div <= MAX_PHOFF_DIV {
             let r0 = if div == 4 { 0b11 << 2 } else { 0 };
             critical_section::with(|cs| {
                 let mut rc = SHARED_I2CBUS.borrow(cs).borrow_mut();
@@ -111,24 +166,24 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
                     &[16, 0xc0, 0xc0], // power down
                     &[
                         42,
-                        // MS0 (a = div * 128 - 512, b = 0, c = 1)
-                        0,
-                        1,
-                        r0,
-                        (p1 >> 1) as u8,
-                        (p1 << 7) as u8,
-                        0,
-                        0,
-                        0,
+                        // MS0 (a = div, b = 0, c = 1)
+                        ms[0],
+                        ms[1],
+                        r0 | ms[2],
+                        ms[3],
+                        ms[4],
+                        ms[5],
+                        ms[6],
+                        ms[7],
                         // MS1 (same as MS0)
-                        0,
-                        1,
-                        r0,
-                        (p1 >> 1) as u8,
-                        (p1 << 7) as u8,
-                        0,
-                        0,
-                        0,
+                        ms[0],
+                        ms[1],
+                        r0 | ms[2],
+                        ms[3],
+                        ms[4],
+                        ms[5],
+                        ms[6],
+                        ms[7],
                     ],
                     &[165, div as u8, 0], // PHOFF
                     &[177, 0xa0],         // pll reset
@@ -146,8 +201,11 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
             // hacky way to set phase offset
             // special thanks: https://tj-lab.org/2020/08/27/si5351%e5%8d%98%e4%bd%93%e3%81%a73mhz%e4%bb%a5%e4%b8%8b%e3%81%ae%e7%9b%b4%e4%ba%a4%e4%bf%a1%e5%8f%b7%e3%82%92%e5%87%ba%e5%8a%9b%e3%81%99%e3%82%8b/
             // T = 1 / 16Hz / 4 = 62.5ms
-            let p1 = div * 128 - 512;
-            let div1 = &TUNE_FINE_QUAD[self.current_div_idx];
+            // under the R divider, a quarter cycle at the output is R quarters at MS0;
+            // the difference is made R times as large
+            let (a, b, c) = self.plan.fine_quad_div(4 << r, self.xtal_mhz());
+            let fine = synth_params(a, b, c);
+            let r0 = r << 4;
             self.alarm.cancel().unwrap();
             self.alarm
                 .schedule(rp2040_hal::fugit::ExtU32::micros(62500_u32))
@@ -158,28 +216,26 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
                 let chunks: &[&[u8]] = &[
                     &[3, 0xff],        // disable all outputs
                     &[16, 0x80, 0x80], // power down
-                    // set PLL 24x
-                    &[26, 0, 1, 0, (20 << 7 >> 8) as u8, (20 << 7) as u8, 0, 0, 0], // MSNA: P1 = 20*128, P2 = 0, P3 = 1
                     &[
                         42,
-                        // MS0: delta 4Hz (P1 = p1 | div1[0]>>24, P2 = div1[0] & 0xffffff, P3 = div1[1])
-                        (div1[1] >> 8) as u8,
-                        div1[1] as u8,
-                        (p1 >> 16) as u8,
-                        (p1 >> 8) as u8,
-                        p1 as u8 | (div1[0] >> 24) as u8,
-                        (div1[1] >> 16 << 4) as u8 | ((div1[0] & 0xff0000) >> 16) as u8,
-                        (div1[0] >> 8) as u8,
-                        div1[0] as u8,
+                        // MS0: slower by 4Hz * R (a + b / c)
+                        fine[0],
+                        fine[1],
+                        r0 | fine[2],
+                        fine[3],
+                        fine[4],
+                        fine[5],
+                        fine[6],
+                        fine[7],
                         // MS1: normal
-                        0,
-                        1,
-                        (p1 >> 16) as u8,
-                        (p1 >> 8) as u8,
-                        p1 as u8,
-                        0,
-                        0,
-                        0,
+                        ms[0],
+                        ms[1],
+                        r0 | ms[2],
+                        ms[3],
+                        ms[4],
+                        ms[5],
+                        ms[6],
+                        ms[7],
                     ],
                     &[165, 0, 0],      // PHOFF
                     &[177, 0xa0],      // pll reset
@@ -199,15 +255,15 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
                 let i2c = rc.as_mut().unwrap();
                 let chunks: &[&[u8]] = &[&[
                     42,
-                    // reset MS0 (P1 = div * 128 - 512, P2 = 0, P3 = 1)
-                    0,
-                    1,
-                    (p1 >> 16) as u8,
-                    (p1 >> 8) as u8,
-                    p1 as u8,
-                    0,
-                    0,
-                    0,
+                    // reset MS0 (a = div, b = 0, c = 1)
+                    ms[0],
+                    ms[1],
+                    r0 | ms[2],
+                    ms[3],
+                    ms[4],
+                    ms[5],
+                    ms[6],
+                    ms[7],
                 ]];
                 for chunk in chunks {
                     i2c.write(Self::I2C_ADDR, chunk)
@@ -219,11 +275,9 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
         Ok(())
     }
 
-    fn set_plla_mul(&mut self, b: u32, c: u32) -> Result<(), Error> {
-        // here we use HW divider
-        let (b, c) = crate::util::gcd::reduce(b, c);
-        let p1: u32 = b * 128 / c - 512;
-        let p2: u32 = b * 128 % c;
+    fn set_plla_mul(&mut self) -> Result<(), Error> {
+        let Plan { a, b, c, .. } = self.plan;
+        let p = synth_params(a, b, c);
 
         critical_section::with(|cs| {
             let mut rc = SHARED_I2CBUS.borrow(cs).borrow_mut();
@@ -232,14 +286,7 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
                 Self::I2C_ADDR,
                 &[
                     26, // MSNA
-                    (c >> 8) as u8,
-                    c as u8,
-                    (p1 >> 16) as u8,
-                    (p1 >> 8) as u8,
-                    p1 as u8,
-                    (c >> 16 << 4) as u8 | (p2 >> 16) as u8,
-                    (p2 >> 8) as u8,
-                    p2 as u8,
+                    p[0], p[1], p[2], p[3], p[4], p[5], p[6], p[7],
                 ],
             )
             .map_err(|_| Error::I2cError)?;
@@ -248,164 +295,120 @@ impl<Alarm: rp2040_hal::timer::Alarm> ClockCtl<Alarm> {
     }
 }
 
-// (DIV, TS, A, B)
-#[rustfmt::skip]
-const TUNE_FACTORS: [TuneFactors; 27] = [
-    TuneFactors::new_div(   4, 10, 1),
-    TuneFactors {
-        div: 6,
-        ts: 10,
-        c: 833_333,
-        _mult: (5_000_000_u32 >> 6).wrapping_mul(XTAL_FREQ_INV_M),
-        _mult_tz: 6,
-    }, // rounded, not exact
-    TuneFactors::new_div(   8, 10, 1),
-    TuneFactors::new_div(  10, 10, 1),
-    TuneFactors::new_div(  12, 10, 3),
-    TuneFactors::new_div(  16,  5, 1),
-    TuneFactors::new_div(  20,  5, 4),
-    TuneFactors::new_div(  25,  1, 1),
-    TuneFactors::new_div(  32,  1, 1),
-    TuneFactors::new_div(  40,  1, 1),
-    TuneFactors::new_div(  50,  1, 1),
-    TuneFactors::new_div(  64,  1, 1),
-    TuneFactors::new_div(  80,  1, 1),
-    TuneFactors::new_div( 100,  1, 1),
-    TuneFactors::new_div( 120,  1, 3),
-    TuneFactors::new_div( 160,  1, 1),
-    TuneFactors::new_div( 200,  1, 1),
-    TuneFactors::new_div( 250,  1, 1),
-    TuneFactors::new_div( 320,  1, 1),
-    TuneFactors::new_div( 400,  1, 1),
-    TuneFactors::new_div( 500,  1, 1),
-    TuneFactors::new_div( 640,  1, 2),
-    TuneFactors::new_div( 800,  1, 1),
-    TuneFactors::new_div(1000,  1, 1),
-    TuneFactors::new_div(1250,  1, 1),
-    TuneFactors::new_div(1600,  1, 1),
-    TuneFactors::new_div(1800,  1, 9),
-];
-
-// constants to make 4hz difference.
-// P1 = [0] >> 24 + (div * 128 - 512)
-// P2 = [0] & 0xffffff
-// P3 = [1]
-const TUNE_FINE_QUAD: [[u32; 2]; 27] = [
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [0, 0],
-    [20480, 937499],
-    [25600, 749999],
-    [32000, 599999],
-    [40960, 468749],
-    [51200, 374999],
-    [64000, 299999],
-    [40960, 117187],
-    [102400, 187499],
-    [128000, 149999],
-    [1 << 24 | 40001, 119999],
-    [2 << 24 | 17302, 93749],
-    [2 << 24 | 191206, 249997],
-];
-
-// find optimal DIV for the target frequency
-#[rustfmt::skip]
-fn find_div_idx(target: HertzU32) -> Option<usize> {
-    if target > HertzU32::MHz(225) { return None; }
-
-    if target < HertzU32::Hz(333334) {
-        // TODO: work with R Divider
+// register values (from the first of 8) of PLL feedback or MultiSynth, a + b / c
+fn synth_params(a: u32, b: u32, c: u32) -> [u8; 8] {
+    let p1 = 128 * a + 128 * b / c - 512;
+    let p2 = 128 * b % c;
+    let p3 = c;
+    [
+        (p3 >> 8) as u8,
+        p3 as u8,
+        (p1 >> 16) as u8 & 0x03,
+        (p1 >> 8) as u8,
+        p1 as u8,
+        (p3 >> 16 << 4) as u8 | (p2 >> 16) as u8 & 0x0f,
+        (p2 >> 8) as u8,
+        p2 as u8,
+    ]
+}
+
+// R divider (log2) to bring the target up into the range of MultiSynth
+fn find_r_div(target: u32) -> Option<u8> {
+    if target < MIN_FREQ {
         return None;
     }
-
-    if target > HertzU32::MHz( 150) { return Some(0); }
-    if target > HertzU32::MHz( 106) { return Some(1); }
-    if target > HertzU32::MHz(  82) { return Some(2); }
-    if target > HertzU32::MHz(  67) { return Some(3); }
-    if target > HertzU32::MHz(  53) { return Some(4); }
-    if target > HertzU32::MHz(  41) { return Some(5); }
-    if target > HertzU32::MHz(  33) { return Some(6); }
-    if target > HertzU32::MHz(  26) { return Some(7); }
-    if target > HertzU32::MHz(  21) { return Some(8); }
-    if target > HertzU32::MHz(  16) { return Some(9); }
-    if target > HertzU32::MHz(  13) { return Some(10); }
-    if target > HertzU32::MHz(  10) { return Some(11); }
-    if target > HertzU32::kHz(8216) { return Some(12); }
-    if target > HertzU32::kHz(6708) { return Some(13); }
-    if target > HertzU32::kHz(5303) { return Some(14); }
-    if target > HertzU32::kHz(4108) { return Some(15); }
-    if target > HertzU32::kHz(3286) { return Some(16); }
-    if target > HertzU32::kHz(2598) { return Some(17); }
-    if target > HertzU32::kHz(2054) { return Some(18); }
-    if target > HertzU32::kHz(1643) { return Some(19); }
-    if target > HertzU32::kHz(1299) { return Some(20); }
-    if target > HertzU32::kHz(1027) { return Some(21); }
-    if target > HertzU32::kHz( 821) { return Some(22); }
-    if target > HertzU32::kHz( 657) { return Some(23); }
-    if target > HertzU32::kHz( 520) { return Some(24); }
-    if target > HertzU32::kHz( 433) { return Some(25); }
-    Some(26)
+    (0..=MAX_R_DIV_LOG2).find(|&r| target << r >= MIN_MS_FREQ)
 }
 
-// F_OUT = F_XTAL * (A + B / C) / div
-#[derive(Clone, Copy)]
-struct TuneFactors {
+/*
+synthesis parameters
+
+F_OUT = F_XTAL * (a + b / c) / div / R, and the target is F_OUT * harmonic
+
+the MultiSynth divider is an even integer, kept as long as the PLL stays in range, as
+changing it takes a reset of the outputs. the PLL ratio is the best approximation of
+F_OUT * div * R / F_XTAL with c up to MAX_DENOM, which is far below 1Hz off. F_XTAL is
+given in mHz, to take the reference correction.
+*/
+#[derive(Clone, Copy, Default)]
+struct Plan {
+    harmonic: u32,
     div: u32,
-    ts: u8,
+    // log2
+    r: u8,
+    a: u32,
+    b: u32,
     c: u32,
-    // for optimal calculation; (A*C + B) === a_mult * F_OUT / F_XTAL
-    _mult: u32,
-    _mult_tz: u8,
 }
 
-const XTAL_FREQ_INV_M: u32 = 585698849; // modular inverse of 25MHz
-const XTAL_FREQ_TRAILING_ZEROS: u8 = 6;
-
-impl TuneFactors {
-    const MIN_MULT: u32 = 24;
-    const MAX_MULT: u32 = 36;
+impl Plan {
+    fn new(target: u32, xtal: u64, current: &Plan) -> Option<Self> {
+        if target > MAX_FREQ {
+            return None;
+        }
+        let harmonic = HARMONICS
+            .into_iter()
+            .find(|&h| target.div_ceil(h) <= MAX_MS_FREQ)?;
+        // R divider is only used below MIN_MS_FREQ
+        let r = if harmonic == 1 {
+            find_r_div(target)?
+        } else {
+            0
+        };
+        let ms = ((target as u64) << r) / harmonic as u64;
+        let in_range = |div: u32| (MIN_VCO..=MAX_VCO).contains(&(ms * div as u64));
+        let div = if current.r == r && in_range(current.div) {
+            current.div
+        } else {
+            // PLL around the middle of the range, leaving room to tune around
+            let mid = (MIN_VCO + MAX_VCO) / 2;
+            let div = (((mid / ms) as u32 + 1) & !1).clamp(MIN_MS_DIV, MAX_MS_DIV);
+            [div, div - 2, div + 2]
+                .into_iter()
+                .find(|&d| (MIN_MS_DIV..=MAX_MS_DIV).contains(&d) && in_range(d))?
+        };
 
-    const fn new_div(div: u32, ts: u8, step: u32) -> Self {
-        assert!(div > 0);
-        assert!(XTAL_FREQ.to_Hz() * step % (div * ts as u32) == 0);
-        let c = XTAL_FREQ.to_Hz() * step / (div * ts as u32);
-        let _mult = div * c;
-        let _mult_tz = _mult.trailing_zeros() as u8;
-        let _mult = (_mult >> _mult_tz).wrapping_mul(XTAL_FREQ_INV_M);
-        Self {
+        // ratio of the PLL in mHz to the reference
+        let num = ((target as u64 * div as u64) << r) * 1000;
+        let den = harmonic as u64 * xtal;
+        let mut a = (num / den) as u32;
+        let (mut b, c) = crate::util::rational::best_approx(num % den, den, MAX_DENOM);
+        if b == c {
+            a += 1;
+            b = 0;
+        }
+        Some(Self {
+            harmonic,
             div,
-            ts,
+            r,
+            a,
+            b,
             c,
-            _mult,
-            _mult_tz,
-        }
+        })
     }
 
-    #[inline]
-    fn get_synth_param(&self, target: HertzU32) -> u32 {
-        if target.to_Hz() * self.div > XTAL_FREQ.to_Hz() * Self::MAX_MULT {
+    // MultiSynth output [mHz]
+    fn ms_mhz(&self, xtal: u64) -> u64 {
+        if self.c == 0 {
             return 0;
-        } // out of range
-        if target.to_Hz() * self.div < XTAL_FREQ.to_Hz() * Self::MIN_MULT {
-            return 0;
-        } // out of range
+        }
+        let num = xtal * (self.a as u64 * self.c as u64 + self.b as u64);
+        let den = self.c as u64 * self.div as u64;
+        (num + den / 2) / den
+    }
+
+    // what the mixer works on [mHz]
+    fn output_mhz(&self, xtal: u64) -> u64 {
+        (self.ms_mhz(xtal) >> self.r) * self.harmonic as u64
+    }
 
-        // target is assumed (ensured) to be divisible by ts
-        let tz: u8 = target.to_Hz().trailing_zeros() as u8;
-        debug_assert!(self._mult_tz + tz >= XTAL_FREQ_TRAILING_ZEROS);
-        let v = (target.to_Hz() >> tz).wrapping_mul(self._mult);
-        v << (self._mult_tz + tz - XTAL_FREQ_TRAILING_ZEROS)
+    // MultiSynth divider slower by delta [Hz] at its output, as (a, b, c)
+    fn fine_quad_div(&self, delta: u32, xtal: u64) -> (u32, u32, u32) {
+        // vco / (div + e) = f - delta, so e = delta * div / (f - delta)
+        let f = self.ms_mhz(xtal);
+        let num = delta * self.div;
+        let den = (f / 1000) as u32 - delta;
+        let (b, c) = crate::util::rational::best_approx((num % den) as u64, den as u64, MAX_DENOM);
+        (self.div + num / den, b, c)
     }
 }
@@ -1,13 +1,17 @@
 use crate::{
     codec::Tx,
+    core::protocol::{AudioRoute, Command, Event},
     dsp::DSPComplex,
     sdr::{
-        demod::{demod_am, demod_fm, DemodMethod},
+        blanker::NoiseBlanker,
+        channel::{mix, Channel},
+        rds::RdsData,
         shift::Shifter,
     },
 };
 use core::cell::Cell;
-use defmt::info;
+use critical_section::Mutex;
+use defmt::warn;
 use rp2040_hal::{
     dma::single_buffer::Config,
     multicore::{self, Multicore, Stack},
@@ -24,9 +28,42 @@ static mut CORE1_STACK: Stack<4096> = Stack::new();
 pub const DEMOD_BUF_SIZE: usize = Shifter::INPUT_SIZE;
 pub type DemodBuffer = [DSPComplex; DEMOD_BUF_SIZE];
 
+// number of demod channels, A and B
+pub const CHANNELS: usize = 2;
+
+// status is reported every this many blocks (1ms each)
+const REPORT_BLOCKS: u32 = 100;
+
+// latest RDS data of FM, per channel; written by core1
+pub static RDS_DATA: [Mutex<Cell<RdsData>>; CHANNELS] =
+    [const { Mutex::new(Cell::new(RdsData::new())) }; CHANNELS];
+
+#[derive(Copy, Clone)]
+pub struct ChannelStatus {
+    pub locked: bool,
+    pub squelch_open: bool,
+    // [dB]
+    pub agc_gain: i8,
+    // channel power [Q8 dBFS]
+    pub level: i32,
+}
+
+// status of core1, reported periodically
+#[derive(Copy, Clone)]
+pub struct DemodStatus {
+    pub channels: [ChannelStatus; CHANNELS],
+    // longest processing time of a block [us]
+    pub time: u16,
+    pub overruns: u8,
+}
+
 // multi-core powered Demodulation
 pub struct DemodTask {
     fifo: SioFifo,
+    // number of commands sent / acknowledged, wrapping in 24 bits
+    sent: u32,
+    acked: u32,
+    status: DemodStatus,
 }
 
 impl DemodTask {
@@ -43,7 +80,21 @@ impl DemodTask {
         core1.spawn(unsafe { &mut CORE1_STACK.mem }, move || {
             core1_task(tx, dma);
         })?;
-        Ok(Self { fifo })
+        Ok(Self {
+            fifo,
+            sent: 0,
+            acked: 0,
+            status: DemodStatus {
+                channels: [ChannelStatus {
+                    locked: false,
+                    squelch_open: true,
+                    agc_gain: 0,
+                    level: 0,
+                }; CHANNELS],
+                time: 0,
+                overruns: 0,
+            },
+        })
     }
 
     // core1 should copy the buffer to its own memory as soon as possible
@@ -53,16 +104,144 @@ impl DemodTask {
         self.fifo.write_blocking(p);
     }
 
-    pub fn set_freq(&mut self, freq: i32) {
-        self.fifo
-            .write_blocking(0x8000_0000 | (freq as u32 & 0xffffff));
+    // to all channels
+    pub fn send(&mut self, command: Command) {
+        self.send_mask((1 << CHANNELS) - 1, command);
+    }
+
+    pub fn send_to(&mut self, channel: usize, command: Command) {
+        self.send_mask(1 << channel, command);
+    }
+
+    fn send_mask(&mut self, channels: u8, command: Command) {
+        self.fifo.write_blocking(command.encode(channels));
+        self.sent = (self.sent + 1) & 0xffffff;
+    }
+
+    // runs f while core1 is kept off flash, e.g. to write it. events meanwhile are dropped
+    pub fn pause<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.send(Command::Pause);
+        let paused = Event::Paused.encode();
+        while self.fifo.read_blocking() != paused {}
+        let r = f();
+        // any word resumes
+        self.fifo.write_blocking(0);
+        r
+    }
+
+    // number of commands not yet processed by core1
+    pub fn pending(&self) -> u32 {
+        self.sent.wrapping_sub(self.acked) & 0xffffff
     }
 
-    pub fn set_method(&mut self, method: DemodMethod) {
-        self.fifo.write_blocking(0x8100_0000 | method as u32);
+    pub fn status(&self) -> &DemodStatus {
+        &self.status
+    }
+
+    // handle events from core1; returns true if status is updated
+    pub fn poll(&mut self) -> bool {
+        let mut updated = false;
+        while let Some(w) = self.fifo.read() {
+            let Some(e) = Event::decode(w) else {
+                warn!("unknown event: {=u32:08x}", w);
+                continue;
+            };
+            let s = &mut self.status;
+            match e {
+                Event::Ack(n) => {
+                    self.acked = n;
+                    continue;
+                }
+                Event::State {
+                    channel,
+                    locked,
+                    squelch_open,
+                    agc_gain,
+                } => {
+                    let Some(c) = s.channels.get_mut(channel as usize) else {
+                        continue;
+                    };
+                    c.locked = locked;
+                    c.squelch_open = squelch_open;
+                    c.agc_gain = agc_gain;
+                }
+                Event::Level { channel, level } => {
+                    let Some(c) = s.channels.get_mut(channel as usize) else {
+                        continue;
+                    };
+                    c.level = level;
+                }
+                Event::Load { time, overruns } => {
+                    s.time = time;
+                    s.overruns = overruns;
+                }
+                Event::Paused => continue,
+            }
+            updated = true;
+        }
+        updated
     }
 }
 
+// events are dropped rather than blocking while core0 is busy
+fn report(fifo: &mut SioFifo, event: Event) {
+    if fifo.is_write_ready() {
+        fifo.write(event.encode());
+    }
+}
+
+// i: index of ch
+fn apply_command(i: usize, ch: &mut Channel, command: Command) {
+    match command {
+        Command::Tune(f) => ch.set_freq(f),
+        Command::Method(m) => {
+            ch.set_method(m);
+            critical_section::with(|cs| RDS_DATA[i].borrow(cs).set(RdsData::new()));
+        }
+        Command::SsbBandwidth(b) => {
+            ch.demods.ssb.set_bandwidth(b);
+            ch.configure();
+        }
+        Command::CwPitch(pitch) => ch.demods.cw.set_pitch(pitch),
+        Command::CwWidth(w) => ch.demods.cw.set_width(w),
+        Command::SamSideband(sb) => ch.demods.sam.set_sideband(sb),
+        Command::FmDeemphasis(t) => ch.demods.fm.set_deemphasis(t),
+        Command::ResetRds => ch.demods.current().reset(),
+        Command::NfmChannel(c) => {
+            ch.demods.nfm.set_channel(c);
+            ch.configure();
+        }
+        Command::Squelch(l) => ch.squelch.set_carrier_level(l),
+        Command::NoiseSquelch(l) => ch.squelch.set_noise_level(l.map(|l| l as i32)),
+        Command::Agc(a) => ch.agc.set_params(a.params()),
+        Command::NoiseReduction(l) => ch.nr.set_level(l),
+        Command::Notch(f) => ch.set_notch(f),
+        Command::AutoNotch(on) => ch.set_auto_notch(on),
+        // not per channel; handled by core1_task
+        Command::Route(_)
+        | Command::NoiseBlanker(_)
+        | Command::BlankerWidth(_)
+        | Command::Pause => {}
+    }
+}
+
+// SIO FIFO registers of this core
+const FIFO_ST: *mut u32 = 0xd000_0050 as *mut u32;
+const FIFO_WR: *mut u32 = 0xd000_0054 as *mut u32;
+const FIFO_RD: *mut u32 = 0xd000_0058 as *mut u32;
+
+// answers `paused`, then spins until a word comes; all in RAM, while core0 writes flash
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn park(paused: u32) {
+    // RDY
+    while FIFO_ST.read_volatile() & 2 == 0 {}
+    FIFO_WR.write_volatile(paused);
+    // VLD
+    while FIFO_ST.read_volatile() & 1 == 0 {}
+    FIFO_RD.read_volatile();
+}
+
 fn core1_task(tx: Tx, dma: Dma) {
     let pac = unsafe { pac::Peripherals::steal() };
     // let core = unsafe { pac::CorePeripherals::steal() };
@@ -70,11 +249,9 @@ fn core1_task(tx: Tx, dma: Dma) {
     let sio = Sio::new(pac.SIO);
     let mut fifo = sio.fifo;
 
-    let mut shifter = crate::sdr::shift::Shifter::new();
-    shifter.set_freq(0);
-
     let buf = cortex_m::singleton!(: DemodBuffer = [DSPComplex::zero(); DEMOD_BUF_SIZE]).unwrap();
     let buf_ds = cortex_m::singleton!(: [DSPComplex; Shifter::OUTPUT_SIZE] = [DSPComplex::zero(); Shifter::OUTPUT_SIZE]).unwrap();
+    let buf_b = cortex_m::singleton!(: [DSPComplex; Shifter::OUTPUT_SIZE] = [DSPComplex::zero(); Shifter::OUTPUT_SIZE]).unwrap();
 
     // to pass 192kHz sampled data
     let mut dmabuf = Cell::new(
@@ -89,28 +266,41 @@ fn core1_task(tx: Tx, dma: Dma) {
     )
     .start();
 
-    let mut method = DemodMethod::AM;
+    // one at a time, not to have both on the stack at once
+    let mut channels: [&mut Channel; CHANNELS] = [
+        cortex_m::singleton!(: Channel = Channel::new()).unwrap(),
+        cortex_m::singleton!(: Channel = Channel::new()).unwrap(),
+    ];
+    let mut route = AudioRoute::Single;
+    // on the input, shared by the channels
+    let mut blanker = NoiseBlanker::new();
+
+    // commands processed so far
+    let mut done: u32 = 0;
+    let mut blocks = 0;
+    let mut max_time: u32 = 0;
+    let mut overruns: u8 = 0;
 
     loop {
         let p = fifo.read_blocking();
 
         if p & 0x8000_0000 != 0 {
-            // read command
-            let c = p >> 24;
-            match c {
-                0x80 => {
-                    // tune
-                    let f = ((p << 8) as i32) >> 8; // sign extend
-
-                    // set freq is the only command now
-                    shifter.set_freq(-f);
-                }
-                0x81 => {
-                    // demodulation method
-                    method = unsafe { DemodMethod::from_u8(p as u8) };
+            match Command::decode(p) {
+                Some((_, Command::Route(r))) => route = r,
+                Some((_, Command::NoiseBlanker(t))) => blanker.set_threshold(t),
+                Some((_, Command::BlankerWidth(w))) => blanker.set_width(w),
+                Some((_, Command::Pause)) => unsafe { park(Event::Paused.encode()) },
+                Some((mask, command)) => {
+                    for (i, ch) in channels.iter_mut().enumerate() {
+                        if mask & (1 << i) != 0 {
+                            apply_command(i, ch, command);
+                        }
+                    }
                 }
-                _ => {}
+                None => warn!("unknown command: {=u32:08x}", p),
             }
+            done = (done + 1) & 0xffffff;
+            report(&mut fifo, Event::Ack(done));
             continue;
         }
 
@@ -121,44 +311,85 @@ fn core1_task(tx: Tx, dma: Dma) {
 
         // copy at first
         buf.copy_from_slice(buffer);
+        blanker.process(buf);
 
-        if !matches!(method, DemodMethod::FM) {
-            shifter.apply(buf, buf_ds);
-            let t2 = unsafe { &*pac::TIMER::PTR }.timerawl.read().bits();
-            info!("shift time: {} us", t2.wrapping_sub(t));
-        }
+        // number of channels in use
+        let active = if route == AudioRoute::Single {
+            1
+        } else {
+            CHANNELS
+        };
 
-        // here demod_**() process into buf2
-        match method {
-            DemodMethod::AM => {
-                demod_am(buf_ds);
-            }
-            DemodMethod::FM => {
-                demod_fm(buf);
-                // downsample
-                for i in (0..buf.len()).step_by(4) {
-                    let mut a: i32 = 0;
-                    for j in 0..4 {
-                        a += buf[i + j].re.0 as i32;
+        channels[0].process(buf, buf_ds);
+        if active > 1 {
+            channels[1].process(buf, buf_b);
+            match route {
+                AudioRoute::Split => {
+                    for (a, b) in buf_ds.iter_mut().zip(buf_b.iter()) {
+                        a.im = b.re;
                     }
-                    a /= 4;
-                    buf_ds[i >> 2].re.0 = a as i16;
                 }
+                _ => mix(buf_ds, buf_b),
+            }
+        }
+        // each to its own, not to overwrite the other
+        for (ch, data) in channels[..active].iter_mut().zip(RDS_DATA.iter()) {
+            if let Some(rds) = ch.demods.current().rds() {
+                critical_section::with(|cs| data.borrow(cs).set(rds));
             }
         }
 
+        // left on upper half
         for (i, x) in buf_ds.iter().enumerate() {
-            let v = x.re.0;
+            let l = x.re.0 as u16 as u32;
+            let r = x.im.0 as u16 as u32;
             for j in 0..4 {
-                dmabuf.get_mut()[i * 4 + j] = ((v as u32) << 16) | v as u32;
+                dmabuf.get_mut()[i * 4 + j] = (l << 16) | r;
             }
         }
 
         let t2 = unsafe { &*pac::TIMER::PTR }.timerawl.read().bits();
-        info!("demod time: {} us", t2.wrapping_sub(t));
+        max_time = max_time.max(t2.wrapping_sub(t));
 
+        // previous block is already out; there was a gap in audio
+        if tfr.is_done() {
+            overruns = overruns.wrapping_add(1);
+        }
         let (dma, buf, tx) = tfr.wait();
         let buff = dmabuf.replace(buf);
         tfr = Config::new(dma, buff, tx).start();
+
+        blocks += 1;
+        if blocks == REPORT_BLOCKS {
+            blocks = 0;
+            for (i, ch) in channels[..active].iter_mut().enumerate() {
+                report(
+                    &mut fifo,
+                    Event::State {
+                        channel: i as u8,
+                        locked: ch.demods.current().locked(),
+                        squelch_open: ch.squelch.is_open(),
+                        agc_gain: (ch.agc.gain_db() >> 8) as i8,
+                    },
+                );
+                report(
+                    &mut fifo,
+                    Event::Level {
+                        channel: i as u8,
+                        level: ch.squelch.power(),
+                    },
+                );
+            }
+            report(
+                &mut fifo,
+                Event::Load {
+                    time: max_time.min(u16::MAX as u32) as u16,
+                    overruns,
+                },
+            );
+            // in case the last one is dropped
+            report(&mut fifo, Event::Ack(done));
+            max_time = 0;
+        }
     }
 }
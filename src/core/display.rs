@@ -1,7 +1,9 @@
 // Screen UI Manager
 
+use crate::core::menu::MenuItem;
 use crate::display::{lcd::LcdDisplay, text};
-use crate::sdr::demod::DemodMethod;
+use crate::dsp::window::Window;
+use crate::sdr::{demod::DemodMethod, refcal::STANDARDS, smeter::s_unit};
 
 pub struct DispManager {
     lcd: LcdDisplay,
@@ -9,18 +11,51 @@ pub struct DispManager {
     spectrum_y: u16,
 }
 
+/*
+screen layout, 320x240
+y0-31:  menu, RDS PS (x0) | freq (x64) | mHz off and harmonic, BFO, demod freq (x224)
+        | options (x282)
+y32:    tune cursors, under the freq and the demod freq
+y40:    core1 status (x0); options go down to y57
+y50:    S-meter (x64)
+y64:    demod freq marker, full width
+y72:    freq scale, full width
+y88-:   waterfall (x32-287)
+*/
 impl DispManager {
     const FREQ_X: u16 = 64;
     const FREQ_Y: u16 = 0;
+    // achieved freq off the target [mHz], then the harmonic
+    const ACHIEVED_X: u16 = 224;
+    const ACHIEVED_Y: u16 = 0;
+    const HARMONIC_X: u16 = 256;
+    const HARMONIC_Y: u16 = 0;
     const TUNE_X: u16 = 224;
     const TUNE_Y: u16 = 24;
     const WF_X: u16 = 32;
-    const WF_Y: u16 = 64;
+    const WF_Y: u16 = 88;
 
     const OPTS_X: u16 = 282;
     const ADCGAIN_Y: u16 = 0;
     const VOL_Y: u16 = 10;
     const METHOD_Y: u16 = 20;
+    const WINDOW_Y: u16 = 30;
+    const SQUELCH_Y: u16 = 40;
+    const AGC_Y: u16 = 50;
+
+    const MENU_X: u16 = 2;
+    const MENU_Y: u16 = 0;
+
+    const RDS_X: u16 = 0;
+    const RDS_Y: u16 = 22;
+
+    const STATUS_X: u16 = 0;
+    const STATUS_Y: u16 = 40;
+
+    // S unit, then the bar; 1px per dB from S0 up to S9+60
+    const SMETER_X: u16 = 64;
+    const SMETER_Y: u16 = 50;
+    const SMETER_W: u16 = 54 + 60;
 
     pub fn new(lcd: LcdDisplay) -> Self {
         Self { lcd, spectrum_y: 0 }
@@ -51,7 +86,8 @@ impl DispManager {
         self.lcd.send_data_iter(renderer);
     }
 
-    pub fn draw_freq(&mut self, freq: u32) {
+    // freq: target [Hz], achieved: what the LO makes of it [mHz]
+    pub fn draw_freq(&mut self, freq: u32, achieved: u64) {
         let mut buf = [0u8; 9];
 
         uint_to_string(freq, &mut buf);
@@ -64,6 +100,13 @@ impl DispManager {
             );
         }
 
+        let mut off = [b' '; 4];
+        int_to_string(
+            (achieved as i64 - freq as i64 * 1000).clamp(-999, 999) as i32,
+            &mut off,
+        );
+        self.draw_text_small(&off, Self::ACHIEVED_X, Self::ACHIEVED_Y);
+
         // waterfall
         // interval = 100kHz
         // in screen = 133px
@@ -92,7 +135,8 @@ impl DispManager {
         }
 
         buf[6] = b'M';
-        let mut f = (freq - 96_000 + 100_000 - 1) / 100_000;
+        // no labels below 0Hz on LF
+        let mut f = ((freq as i32 - 96_000 + 100_000 - 1) / 100_000).max(0) as u32;
         let mut x =
             (Self::WF_X as i32 + 128 + ((f * 100_000) as i32 - freq as i32) * 256 / 192000) as u16;
         while f * 100_000 < freq + 96_000 {
@@ -107,8 +151,21 @@ impl DispManager {
         }
     }
 
-    pub fn draw_demod_freq(&mut self, freq: i32) {
+    // LO harmonic in use, right after the freq; nothing on the fundamental
+    pub fn draw_harmonic(&mut self, harmonic: u8) {
+        let t = match harmonic {
+            1 => b"   ",
+            3 => b"3RD",
+            _ => b"5TH",
+        };
+        self.draw_text_small(t, Self::HARMONIC_X, Self::HARMONIC_Y);
+    }
+
+    // bfo: BFO pitch to show above the demod freq, if any
+    // `other`: demod freq of the other channel while dual watching, marked in cyan
+    pub fn draw_demod_freq(&mut self, freq: i32, bfo: Option<u32>, other: Option<i32>) {
         let x = 160_u16.wrapping_add_signed((freq / 750) as i16);
+        let xo = other.map(|f| 160_u16.wrapping_add_signed((f / 750) as i16));
 
         self.lcd
             .set_window(0, Self::WF_Y - 24, LcdDisplay::LCD_WIDTH, 8);
@@ -117,6 +174,8 @@ impl DispManager {
             for i in 0..LcdDisplay::LCD_WIDTH {
                 if i.abs_diff(x) <= j / 2 {
                     self.lcd.send_data_unchecked(&[0xff, 0xff]);
+                } else if xo.is_some_and(|xo| i.abs_diff(xo) <= j / 2) {
+                    self.lcd.send_data_unchecked(&[0x07, 0xff]);
                 } else {
                     self.lcd.send_data_unchecked(&[0, 0]);
                 }
@@ -126,6 +185,23 @@ impl DispManager {
         let mut buf = [0u8; 6];
         int_to_string(freq, &mut buf);
         self.draw_text_small(&buf, Self::TUNE_X, Self::TUNE_Y);
+
+        let mut buf = [b' '; 7];
+        if let Some(bfo) = bfo {
+            buf[..3].copy_from_slice(b"BFO");
+            uint_to_string(bfo, &mut buf[3..]);
+        }
+        self.draw_text_small(&buf, Self::TUNE_X, Self::TUNE_Y - 10);
+    }
+
+    // selected demod channel, right after the demod freq; None for single
+    pub fn draw_channel(&mut self, channel: Option<usize>) {
+        let t = match channel {
+            None => b" ",
+            Some(0) => b"A",
+            Some(_) => b"B",
+        };
+        self.draw_text_small(t, Self::TUNE_X + 8 * 6, Self::TUNE_Y);
     }
 
     pub fn draw_adc_gain(&mut self, gain: i8) {
@@ -141,11 +217,234 @@ impl DispManager {
     }
 
     pub fn draw_method(&mut self, method: DemodMethod) {
-        let t = match method {
-            DemodMethod::AM => b"AM",
-            DemodMethod::FM => b"FM",
+        self.draw_text_small(method.mode().name, Self::OPTS_X, Self::METHOD_Y);
+    }
+
+    // lock indicator of the demodulator, right after the method
+    // SAM: carrier lock, FM: stereo
+    pub fn draw_lock(&mut self, method: DemodMethod, locked: bool) {
+        let t = match (locked, method) {
+            (false, _) => b" ",
+            (true, DemodMethod::FM) => b"S",
+            (true, _) => b"*",
         };
-        self.draw_text_small(t, Self::OPTS_X, Self::METHOD_Y);
+        self.draw_text_small(t, Self::OPTS_X + 8 * 3, Self::METHOD_Y);
+    }
+
+    pub fn draw_window(&mut self, window: Window) {
+        self.draw_text_small(window.name(), Self::OPTS_X, Self::WINDOW_Y);
+    }
+
+    // carrier squelch level [dBFS]
+    pub fn draw_squelch(&mut self, level: Option<i32>) {
+        let mut buf = [b' '; 3];
+        match level {
+            Some(l) => {
+                int_to_string(l, &mut buf);
+            }
+            None => buf.copy_from_slice(b"OFF"),
+        }
+        self.draw_text_small(&buf, Self::OPTS_X, Self::SQUELCH_Y);
+    }
+
+    // audio AGC gain [dB]; None if AGC is off
+    pub fn draw_agc_gain(&mut self, gain: Option<i32>) {
+        let mut buf = [b' '; 3];
+        match gain {
+            Some(g) => {
+                int_to_string(g, &mut buf);
+            }
+            None => buf.copy_from_slice(b"OFF"),
+        }
+        self.draw_text_small(&buf, Self::OPTS_X, Self::AGC_Y);
+    }
+
+    // busy indicator, right after the squelch level
+    pub fn draw_squelch_open(&mut self, open: bool) {
+        let t = if open { b"*" } else { b" " };
+        self.draw_text_small(t, Self::OPTS_X + 8 * 3, Self::SQUELCH_Y);
+    }
+
+    // core1 status in a line: channel power [dBFS], processing time of a block [us], overruns
+    pub fn draw_status(&mut self, level: i32, time: u16, overruns: u8) {
+        let mut buf = [b' '; 19];
+        int_to_string(level.max(-999), &mut buf[..4]);
+        buf[4..6].copy_from_slice(b"dB");
+        uint_to_string(time as u32, &mut buf[7..11]);
+        buf[11..13].copy_from_slice(b"us");
+        buf[14..16].copy_from_slice(b"OV");
+        uint_to_string(overruns as u32, &mut buf[16..]);
+        self.draw_text_small(&buf, Self::STATUS_X, Self::STATUS_Y);
+    }
+
+    // level and peak [dBm]; s9: S9 level of the band [dBm]
+    pub fn draw_smeter(&mut self, level: i32, peak: i32, s9: i32) {
+        let mut buf = [b' '; 5];
+        let (s, over) = s_unit(level, s9);
+        buf[0] = b'S';
+        buf[1] = b'0' + s;
+        if over > 0 {
+            buf[2] = b'+';
+            uint_to_string(over.min(99) as u32, &mut buf[3..]);
+        }
+        self.draw_text_small(&buf, Self::SMETER_X, Self::SMETER_Y);
+
+        let s0 = s9 - 54;
+        let x = (level - s0).clamp(0, Self::SMETER_W as i32) as u16;
+        let xp = (peak - s0).clamp(0, Self::SMETER_W as i32) as u16;
+        self.lcd
+            .set_window(Self::SMETER_X + 8 * 6, Self::SMETER_Y + 1, Self::SMETER_W, 6);
+        self.lcd.send_data(&[]); // dummy
+        for j in 0..6 {
+            for i in 0..Self::SMETER_W {
+                let c = if i == xp && xp > 0 {
+                    [0xff, 0xe0] // peak: yellow
+                } else if i < x && i < 54 {
+                    [0x07, 0xe0] // green up to S9
+                } else if i < x {
+                    [0xf8, 0x00] // red over S9
+                } else if j == 5 && (if i <= 54 { i % 6 } else { (i - 54) % 10 }) == 0 {
+                    [0x84, 0x10] // scale: S units, then 10dB
+                } else {
+                    [0, 0]
+                };
+                self.lcd.send_data_unchecked(&c);
+            }
+        }
+    }
+
+    // RDS program service name
+    pub fn draw_rds_ps(&mut self, ps: &[u8; 8]) {
+        self.draw_text_small(ps, Self::RDS_X, Self::RDS_Y);
+    }
+
+    pub fn draw_menu(&mut self, item: MenuItem, value: i32) {
+        self.draw_text_small(item.name(), Self::MENU_X, Self::MENU_Y);
+
+        let mut buf = [b' '; 7];
+        match item {
+            MenuItem::SsbBandwidth => {
+                // x.xk
+                let v = value as u32 / 100;
+                buf[0] = (v / 10 % 10) as u8 + b'0';
+                buf[1] = b'.';
+                buf[2] = (v % 10) as u8 + b'0';
+                buf[3] = b'k';
+            }
+            MenuItem::CwPitch | MenuItem::CwWidth => {
+                uint_to_string(value as u32, &mut buf[..4]);
+                buf[4..6].copy_from_slice(b"Hz");
+            }
+            MenuItem::Agc => {
+                buf[..4].copy_from_slice(match value {
+                    0 => b"OFF ",
+                    1 => b"SLOW",
+                    _ => b"FAST",
+                });
+            }
+            MenuItem::NoiseSquelch => {
+                if value == 0 {
+                    buf[..3].copy_from_slice(b"OFF");
+                } else {
+                    uint_to_string(value as u32, &mut buf[..2]);
+                    buf[2..4].copy_from_slice(b"dB");
+                }
+            }
+            MenuItem::NfmChannel => {
+                // xx.xk
+                let v = value as u32 / 100;
+                uint_to_string(v / 10, &mut buf[..2]);
+                buf[2] = b'.';
+                buf[3] = (v % 10) as u8 + b'0';
+                buf[4] = b'k';
+            }
+            MenuItem::FmDeemphasis => {
+                uint_to_string(value as u32, &mut buf[..2]);
+                buf[2..4].copy_from_slice(b"us");
+            }
+            MenuItem::SamSideband => {
+                buf[..3].copy_from_slice(match value {
+                    0 => b"DSB",
+                    1 => b"USB",
+                    _ => b"LSB",
+                });
+            }
+            MenuItem::DualWatch => {
+                buf[..5].copy_from_slice(match value {
+                    0 => b"OFF  ",
+                    1 => b"SPLIT",
+                    _ => b"MIX  ",
+                });
+            }
+            MenuItem::Channel => buf[0] = b'A' + value as u8,
+            MenuItem::NoiseBlanker => {
+                if value == 0 {
+                    buf[..3].copy_from_slice(b"OFF");
+                } else {
+                    buf[0] = b'x';
+                    uint_to_string(value as u32, &mut buf[1..3]);
+                }
+            }
+            MenuItem::BlankerWidth => {
+                uint_to_string(value as u32, &mut buf[..3]);
+                buf[3..5].copy_from_slice(b"us");
+            }
+            MenuItem::NoiseReduction => {
+                buf[..4].copy_from_slice(match value {
+                    0 => b"OFF ",
+                    1 => b"LOW ",
+                    2 => b"MID ",
+                    _ => b"HIGH",
+                });
+            }
+            MenuItem::Notch => {
+                if value == 0 {
+                    buf[..3].copy_from_slice(b"OFF");
+                } else {
+                    uint_to_string(value as u32, &mut buf[..4]);
+                    buf[4..6].copy_from_slice(b"Hz");
+                }
+            }
+            MenuItem::AutoNotch => {
+                buf[..3].copy_from_slice(if value != 0 { b"ON " } else { b"OFF" });
+            }
+            MenuItem::IqCorrection => {
+                buf[..4].copy_from_slice(match value {
+                    0 => b"OFF ",
+                    1 => b"AUTO",
+                    _ => b"HOLD",
+                });
+            }
+            MenuItem::CalCarrier => {
+                if value == 0 {
+                    buf[..5].copy_from_slice(b"TUNED");
+                } else {
+                    // xx.xM
+                    let v = STANDARDS[value as usize - 1] / 100_000;
+                    uint_to_string(v / 10, &mut buf[..2]);
+                    buf[2] = b'.';
+                    buf[3] = (v % 10) as u8 + b'0';
+                    buf[4] = b'M';
+                }
+            }
+            MenuItem::AutoCal => {
+                buf[..5].copy_from_slice(if value != 0 { b"...  " } else { b"START" });
+            }
+            MenuItem::CalFailed => {
+                buf[..5].copy_from_slice(match value {
+                    0 => b"NONE ",
+                    1 => b"WEAK ",
+                    2 => b"DRIFT",
+                    _ => b"RANGE",
+                });
+            }
+            MenuItem::RefPpb | MenuItem::ZeroBeat | MenuItem::CalConfirm => {
+                // sign next to the digits
+                let i = uint_to_string(value.unsigned_abs(), &mut buf[1..]);
+                buf[i] = if value < 0 { b'-' } else { b'+' };
+            }
+        }
+        self.draw_text_small(&buf, Self::MENU_X, Self::MENU_Y + 10);
     }
 
     /*
@@ -155,6 +454,10 @@ impl DispManager {
     13: adc gain
     14: volume
     15: method
+    16: fft window
+    17: squelch
+    18: menu item
+    19: menu value
     */
     pub fn draw_cursor(&mut self, cursor: u8) {
         // tune digit
@@ -180,8 +483,15 @@ impl DispManager {
             );
         }
 
-        self.lcd.set_window(Self::OPTS_X - 1, 0, 1, 40);
-        for i in 13..16 {
+        self.lcd.set_window(Self::OPTS_X - 1, 0, 1, 50);
+        for i in 13..18 {
+            self.lcd.send_data_iter(
+                core::iter::repeat(if cursor == i { 0xff } else { 0x00 }).take(10 * 2),
+            );
+        }
+
+        self.lcd.set_window(Self::MENU_X - 1, Self::MENU_Y, 1, 20);
+        for i in 18..20 {
             self.lcd.send_data_iter(
                 core::iter::repeat(if cursor == i { 0xff } else { 0x00 }).take(10 * 2),
             );
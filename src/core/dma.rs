@@ -17,6 +17,13 @@ pub static mut DMA_TFR: Option<
 > = None;
 
 pub static FFT_READY: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+// times DMABUF is filled up, to tell sample time
+pub static ROUNDS: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+// (rounds, start of what the round in progress has not overwritten yet)
+pub fn position() -> (u32, usize) {
+    critical_section::with(|cs| (ROUNDS.borrow(cs).get(), unsafe { DMA_IDX } + DMA_CHUNK_LEN))
+}
 
 #[allow(non_snake_case)]
 #[interrupt]
@@ -39,6 +46,8 @@ fn DMA_IRQ_0() {
     if *idx >= DMABUF_LEN {
         critical_section::with(|cs| {
             FFT_READY.borrow(cs).set(true);
+            let r = ROUNDS.borrow(cs);
+            r.set(r.get().wrapping_add(1));
         });
         *idx = 0;
     }
@@ -1,15 +1,27 @@
 use crate::{
-    board, codec,
+    board,
+    clockctl::{ClockCtl, MAX_PPB},
+    codec,
     core::{
-        demod::{self, DEMOD_BUF_SIZE},
+        demod::{self, CHANNELS, DEMOD_BUF_SIZE, RDS_DATA},
         display::DispManager,
-        dma::DMABUF_LEN,
+        dma::{self, DMABUF_LEN},
+        menu::{MenuItem, Settings},
+        protocol::{AudioRoute, Command},
     },
     display::lcd::LcdDisplay,
-    dsp::{self, DSPComplex},
+    dsp::{fft::FFT, window::Window, DSPComplex},
     hal,
     i2c::SHARED_I2CBUS,
-    sdr::demod::DemodMethod,
+    sdr::{
+        agc::AgcPreset,
+        demod::DemodMethod,
+        iq::{IqCorrection, IqMode},
+        refcal::{self, CalFault, CarrierSearch, PhaseTrack},
+        smeter::{self, SMeter},
+        squelch::Squelch,
+    },
+    storage::{self, Calibration},
 };
 use defmt::*;
 use hal::{
@@ -48,8 +60,6 @@ pub fn main() -> ! {
         &mut pac.RESETS,
     );
 
-    crate::dsp::fft::make_sequential_expi();
-
     let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().to_Hz());
 
     // init shared i2c
@@ -72,13 +82,13 @@ pub fn main() -> ! {
 
     let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
 
-    let mut clockctl = crate::clockctl::ClockCtl::new(timer.alarm_0().unwrap());
+    let mut clockctl = ClockCtl::new(timer.alarm_0().unwrap());
     clockctl
         .init()
         .unwrap_or_else(|e| info!("Failed to initialize clockctl: {}", e));
-    clockctl
-        .tune(81300.kHz())
-        .unwrap_or_else(|e| info!("Failed to tune: {}", e));
+    if let Err(e) = clockctl.tune(81300.kHz()) {
+        info!("Failed to tune: {}", e);
+    }
 
     crate::control::init(
         pins.rotary_a.reconfigure(),
@@ -145,16 +155,44 @@ pub fn main() -> ! {
 
     const FFTBUF_LEN: usize = 256;
     let mut fft_buf = [DSPComplex::zero(); FFTBUF_LEN];
+    let mut fft = FFT::<FFTBUF_LEN>::new(Window::Hann);
+    // calibration interpolates on Hann, whatever is shown
+    let cal_fft = FFT::<FFTBUF_LEN>::new(Window::Hann);
+    let mut cal_run: Option<CalRun<FFTBUF_LEN>> = None;
+    // where the result of a run goes back to
+    let mut cal_back = MenuItem::AutoCal;
 
     let mut t = timer.get_counter_low();
 
-    let mut demod_tune: i32 = 0;
     let mut adc_gain: i8 = 30;
     codec.set_adc_gain(adc_gain);
     let mut dac_gain: i16 = 0;
     codec.set_dac_volume(dac_gain);
 
-    let mut method = DemodMethod::AM;
+    // controls act on settings.channel; status is shown for it
+    let mut channels = [ChannelState::new(); CHANNELS];
+    let mut locked = false;
+    let mut rds_ps = [b' '; 8];
+    let mut squelch_open = true;
+    let mut agc_gain = i32::MIN;
+    let mut meter = SMeter::new();
+    let mut harmonic = 0;
+
+    let mut menu_item = MenuItem::SsbBandwidth;
+    let mut settings = Settings::new();
+
+    // on a copy of the raw input, for both FFT and demod
+    let mut iq = IqCorrection::new();
+    // DMA fills DMABUF again meanwhile; core1 reads this until the next one
+    static mut IQBUF: [DSPComplex; DMABUF_LEN] = [DSPComplex::zero(); DMABUF_LEN];
+    if let Some(cal) = storage::load() {
+        iq.set_coefficients(cal.iq_phase, cal.iq_gain);
+        settings.iq = IqMode::Hold;
+        settings.ref_ppb = cal.ref_ppb;
+    }
+    iq.set_mode(settings.iq);
+    set_ppb(&mut clockctl, &mut display, &mut settings);
+    let mut saved_ppb = settings.ref_ppb;
 
     const TS_TBL: [u32; 9] = [
         1,
@@ -168,17 +206,29 @@ pub fn main() -> ! {
         100_000_000,
     ];
     let mut cursor = 0;
-    const CURSOR_MOD: u8 = 16;
+    const CURSOR_MOD: u8 = 20;
 
-    display.draw_freq(clockctl.get_current_freq().to_Hz());
+    display.draw_freq(
+        clockctl.get_current_freq().to_Hz(),
+        clockctl.get_achieved_freq_mhz(),
+    );
     display.draw_cursor(cursor);
-    display.draw_demod_freq(demod_tune);
     display.draw_adc_gain(adc_gain);
     display.draw_volume(dac_gain);
-    display.draw_method(method);
+    display.draw_window(fft.window());
+    draw_channel(&mut display, &channels, &settings);
+    demod.send(Command::Agc(settings.agc()));
+    display.draw_menu(menu_item, settings.value(menu_item));
 
     // main loop
     loop {
+        // whoever tuned
+        if clockctl.harmonic() != harmonic {
+            harmonic = clockctl.harmonic();
+            iq.set_swap(clockctl.iq_swapped());
+            display.draw_harmonic(harmonic);
+        }
+
         {
             let fft_ready = critical_section::with(|cs| {
                 // if true, set false and return
@@ -186,25 +236,137 @@ pub fn main() -> ! {
             });
 
             if fft_ready {
+                let (round, _) = dma::position();
+                let iq_buf = unsafe { &mut IQBUF };
+                iq_buf.copy_from_slice(unsafe {
+                    core::slice::from_raw_parts(
+                        crate::core::dma::DMABUF.as_ptr() as *const DSPComplex,
+                        DMABUF_LEN,
+                    )
+                });
+                // the copy from here is of the last round, in one piece
+                let (r, from) = dma::position();
+                iq.process(iq_buf);
+
                 // send buffer to core1, for demodulation
                 for i in 0..DMABUF_LEN / DEMOD_BUF_SIZE {
                     demod.send_buffer(unsafe {
-                        &*(crate::core::dma::DMABUF.as_ptr().add(i * DEMOD_BUF_SIZE)
+                        &*(iq_buf.as_ptr().add(i * DEMOD_BUF_SIZE)
                             as *const [DSPComplex; DEMOD_BUF_SIZE])
                     });
                 }
 
-                fft_buf.copy_from_slice(unsafe {
-                    core::slice::from_raw_parts(
-                        crate::core::dma::DMABUF.as_ptr() as *const DSPComplex,
-                        256,
-                    )
-                });
-                dsp::fft::fft(&mut fft_buf);
+                fft_buf.copy_from_slice(&iq_buf[..FFTBUF_LEN]);
+                if let Some(run) = cal_run.as_mut() {
+                    cal_fft.process(&mut fft_buf);
+                    let done = match run.track.as_mut() {
+                        // roughly in the spectrum first
+                        None => {
+                            run.search.push(&fft_buf)
+                                && match run.search.carrier() {
+                                    Ok(hz) => {
+                                        run.track =
+                                            Some(PhaseTrack::new(hz as i64 * 1000, CAL_BLOCKS));
+                                        false
+                                    }
+                                    Err(_) => true,
+                                }
+                        }
+                        // then by its phase
+                        Some(track) => {
+                            r == round
+                                && from < DMABUF_LEN
+                                && track.push(
+                                    round
+                                        .wrapping_sub(1)
+                                        .wrapping_mul(DMABUF_LEN as u32)
+                                        .wrapping_add(from as u32),
+                                    &iq_buf[from..],
+                                )
+                        }
+                    };
+                    if done {
+                        let run = cal_run.take().unwrap();
+                        settings.calibrating = false;
+                        if let Some(f) = run.restore {
+                            tune(&mut clockctl, &mut display, f);
+                        }
+                        let measured = match &run.track {
+                            Some(track) => track.carrier(),
+                            // not found in the spectrum
+                            None => run.search.carrier().map(|hz| hz as i64 * 1000),
+                        };
+                        let result = measured.and_then(|mhz| {
+                            let err = refcal::ppb_error(mhz, run.expected, run.lo);
+                            info!("Calibration: carrier at {} mHz, {} ppb off", mhz, err);
+                            if err.abs() < MAX_PPB {
+                                Ok(err)
+                            } else {
+                                // not the carrier, or another one
+                                Err(CalFault::OutOfRange)
+                            }
+                        });
+                        cal_back = if run.restore.is_some() {
+                            MenuItem::AutoCal
+                        } else {
+                            MenuItem::ZeroBeat
+                        };
+                        match result {
+                            // auto calibration; applied once confirmed
+                            Ok(err) if run.restore.is_some() => {
+                                settings.cal_error = err;
+                                menu_item = MenuItem::CalConfirm;
+                            }
+                            Ok(err) => {
+                                settings.ref_ppb += err;
+                                set_ppb(&mut clockctl, &mut display, &mut settings);
+                            }
+                            Err(fault) => {
+                                info!("Calibration: not taken, {}", Debug2Format(&fault));
+                                settings.cal_fault = fault;
+                                menu_item = MenuItem::CalFailed;
+                            }
+                        }
+                        display.draw_menu(menu_item, settings.value(menu_item));
+                    }
+                } else {
+                    fft.process(&mut fft_buf);
+                }
                 display.draw_spectrum(&fft_buf);
             }
         }
 
+        // demod status
+        if demod.poll() {
+            let st = *demod.status();
+            let cst = st.channels[settings.channel];
+            if cst.locked != locked {
+                locked = cst.locked;
+                display.draw_lock(channels[settings.channel].method, locked);
+            }
+
+            if cst.squelch_open != squelch_open {
+                squelch_open = cst.squelch_open;
+                display.draw_squelch_open(squelch_open);
+            }
+
+            let g = cst.agc_gain as i32;
+            if settings.agc() != AgcPreset::Off && g != agc_gain {
+                agc_gain = g;
+                display.draw_agc_gain(Some(agc_gain));
+            }
+
+            display.draw_status(cst.level >> 8, st.time, st.overruns);
+
+            let freq = clockctl.get_current_freq().to_Hz() as i32 + channels[settings.channel].tune;
+            meter.update(smeter::dbm(cst.level, adc_gain, freq as u32));
+            display.draw_smeter(
+                meter.level() >> 8,
+                meter.peak() >> 8,
+                smeter::s9_level(freq as u32),
+            );
+        }
+
         // control
         let (rot, btn) = crate::control::fetch_inputs();
         if btn != 0 {
@@ -223,20 +385,19 @@ pub fn main() -> ! {
             match cursor {
                 0..=3 => {
                     // demod tune
-                    demod_tune += rot * TS_TBL[(cursor) as usize + 1] as i32;
-                    demod_tune = demod_tune.clamp(-96000, 96000);
-                    demod.set_freq(demod_tune);
-                    display.draw_demod_freq(demod_tune);
+                    let c = &mut channels[settings.channel];
+                    c.tune += rot * TS_TBL[(cursor) as usize + 1] as i32;
+                    c.tune = c.tune.clamp(-96000, 96000);
+                    demod.send_to(settings.channel, Command::Tune(c.tune));
+                    draw_tune(&mut display, &channels, &settings);
                 }
                 4..=12 => {
                     // tune
                     let f = clockctl.get_current_freq();
-                    let tune_step =
-                        TS_TBL[cursor as usize - 4].max(clockctl.get_tune_step() as u32);
+                    let tune_step = TS_TBL[cursor as usize - 4];
                     let f = f.to_Hz().wrapping_add(rot as u32 * tune_step);
-                    match clockctl.tune(hal::fugit::HertzU32::Hz(f)) {
-                        Err(e) => info!("Failed to tune: {}", e),
-                        Ok(_) => display.draw_freq(f),
+                    if tune(&mut clockctl, &mut display, f).is_some() {
+                        demod.send(Command::ResetRds);
                     }
                 }
                 13 => {
@@ -250,15 +411,160 @@ pub fn main() -> ! {
                     display.draw_volume(dac_gain);
                 }
                 15 => {
-                    method = unsafe {
-                        DemodMethod::from_u8(
-                            (method as u8 as i8 + rot as i8)
-                                .wrapping_rem_euclid(DemodMethod::METHOD_COUNT as i8)
-                                as u8,
-                        )
-                    };
-                    demod.set_method(method);
+                    let c = &mut channels[settings.channel];
+                    c.method = c.method.rotate(rot);
+                    let method = c.method;
+                    demod.send_to(settings.channel, Command::Method(method));
+                    settings.set_method(method);
+                    demod.send_to(settings.channel, Command::Agc(settings.agc()));
+                    display.draw_agc_gain(None);
+                    agc_gain = i32::MIN;
                     display.draw_method(method);
+                    locked = false;
+                    display.draw_lock(method, locked);
+                    draw_tune(&mut display, &channels, &settings);
+                    display.draw_menu(menu_item, settings.value(menu_item));
+                }
+                16 => {
+                    let i = (fft.window() as i32 + rot).rem_euclid(Window::ALL.len() as i32);
+                    fft.set_window(Window::ALL[i as usize]);
+                    display.draw_window(fft.window());
+                }
+                17 => {
+                    let c = &mut channels[settings.channel];
+                    c.squelch = (c.squelch + rot)
+                        .clamp(Squelch::MIN_CARRIER_LEVEL - 1, Squelch::MAX_CARRIER_LEVEL);
+                    demod.send_to(settings.channel, Command::Squelch(squelch_level(c.squelch)));
+                    display.draw_squelch(squelch_level(c.squelch));
+                }
+                18 => {
+                    // done with the reference; keep it
+                    if matches!(menu_item, MenuItem::RefPpb | MenuItem::ZeroBeat)
+                        && settings.ref_ppb != saved_ppb
+                    {
+                        save_calibration(&mut demod, &iq, settings.ref_ppb);
+                        saved_ppb = settings.ref_ppb;
+                    }
+                    // leaving the confirmation discards the result
+                    menu_item = if matches!(menu_item, MenuItem::CalConfirm | MenuItem::CalFailed) {
+                        cal_back
+                    } else {
+                        menu_item.rotate(rot)
+                    };
+                    display.draw_menu(menu_item, settings.value(menu_item));
+                }
+                19 => {
+                    settings.adjust(menu_item, rot);
+                    match menu_item {
+                        MenuItem::Agc => {
+                            // per method; also to the other channel in the same method
+                            let method = channels[settings.channel].method;
+                            for (i, c) in channels.iter().enumerate() {
+                                if c.method == method {
+                                    demod.send_to(i, Command::Agc(settings.agc()));
+                                }
+                            }
+                            display.draw_agc_gain(None);
+                            agc_gain = i32::MIN;
+                        }
+                        MenuItem::SsbBandwidth => {
+                            demod.send(Command::SsbBandwidth(settings.ssb_bandwidth))
+                        }
+                        MenuItem::CwPitch => {
+                            demod.send(Command::CwPitch(settings.cw_pitch));
+                            draw_tune(&mut display, &channels, &settings);
+                        }
+                        MenuItem::CwWidth => demod.send(Command::CwWidth(settings.cw_width)),
+                        MenuItem::SamSideband => {
+                            demod.send(Command::SamSideband(settings.sam_sideband))
+                        }
+                        MenuItem::FmDeemphasis => {
+                            demod.send(Command::FmDeemphasis(settings.fm_deemphasis))
+                        }
+                        MenuItem::NfmChannel => {
+                            demod.send(Command::NfmChannel(settings.nfm_channel))
+                        }
+                        MenuItem::NoiseSquelch => demod.send(Command::NoiseSquelch(
+                            (settings.noise_squelch > 0).then_some(settings.noise_squelch),
+                        )),
+                        MenuItem::NoiseBlanker => demod.send(Command::NoiseBlanker(
+                            (settings.nb_threshold > 0).then_some(settings.nb_threshold),
+                        )),
+                        MenuItem::BlankerWidth => {
+                            demod.send(Command::BlankerWidth(settings.nb_width))
+                        }
+                        MenuItem::NoiseReduction => {
+                            demod.send(Command::NoiseReduction(settings.nr))
+                        }
+                        MenuItem::Notch => demod.send(Command::Notch(
+                            (settings.notch > 0).then_some(settings.notch),
+                        )),
+                        MenuItem::AutoNotch => demod.send(Command::AutoNotch(settings.auto_notch)),
+                        MenuItem::IqCorrection => {
+                            // calibration run is over; keep the result
+                            if iq.mode() == IqMode::Track && settings.iq == IqMode::Hold {
+                                save_calibration(&mut demod, &iq, settings.ref_ppb);
+                                saved_ppb = settings.ref_ppb;
+                            }
+                            iq.set_mode(settings.iq);
+                        }
+                        MenuItem::RefPpb => set_ppb(&mut clockctl, &mut display, &mut settings),
+                        // carrier at the demod freq of the channel; applied at once
+                        MenuItem::ZeroBeat if cal_run.is_none() => {
+                            let lo = clockctl.get_current_freq().to_Hz();
+                            let tune = channels[settings.channel].tune;
+                            cal_run = Some(CalRun {
+                                search: CarrierSearch::new(tune, 0, CAL_FRAMES),
+                                track: None,
+                                lo,
+                                expected: (lo as i64 + tune as i64) * 1000
+                                    - clockctl.get_achieved_freq_mhz() as i64,
+                                restore: None,
+                            });
+                        }
+                        MenuItem::AutoCal if cal_run.is_none() => {
+                            let f = clockctl.get_current_freq().to_Hz();
+                            let carrier = match settings.cal_carrier {
+                                0 => f,
+                                i => refcal::STANDARDS[i - 1],
+                            };
+                            // off DC, where the IQ correction takes away
+                            let lo = carrier.saturating_sub(CAL_OFFSET as u32);
+                            if let Some(achieved) = tune(&mut clockctl, &mut display, lo) {
+                                cal_run = Some(CalRun {
+                                    search: CarrierSearch::new(CAL_OFFSET, CAL_SETTLE, CAL_FRAMES),
+                                    track: None,
+                                    lo,
+                                    expected: carrier as i64 * 1000 - achieved as i64,
+                                    restore: Some(f),
+                                });
+                                settings.calibrating = true;
+                            }
+                        }
+                        MenuItem::CalConfirm => {
+                            // right to apply and save, left to discard
+                            if rot > 0 {
+                                settings.ref_ppb += settings.cal_error;
+                                set_ppb(&mut clockctl, &mut display, &mut settings);
+                                save_calibration(&mut demod, &iq, settings.ref_ppb);
+                                saved_ppb = settings.ref_ppb;
+                            }
+                            menu_item = cal_back;
+                        }
+                        MenuItem::CalFailed => menu_item = cal_back,
+                        MenuItem::ZeroBeat | MenuItem::AutoCal | MenuItem::CalCarrier => {}
+                        MenuItem::DualWatch | MenuItem::Channel => {
+                            if menu_item == MenuItem::DualWatch {
+                                demod.send(Command::Route(settings.route));
+                            }
+                            settings.set_method(channels[settings.channel].method);
+                            locked = false;
+                            squelch_open = true;
+                            agc_gain = i32::MIN;
+                            draw_channel(&mut display, &channels, &settings);
+                        }
+                    }
+                    display.draw_menu(menu_item, settings.value(menu_item));
                 }
                 _ => core::unreachable!(),
             }
@@ -268,7 +574,145 @@ pub fn main() -> ! {
         let tt = timer.get_counter_low();
         if tt.wrapping_sub(t) > 1_000_000 {
             info!("AGC status: {}", codec.get_agc_gain());
+            let st = demod.status();
+            info!(
+                "demod: {} us/block, {} overruns, {} commands pending",
+                st.time,
+                st.overruns,
+                demod.pending()
+            );
+
+            // of the channel under control
+            let rds = critical_section::with(|cs| RDS_DATA[settings.channel].borrow(cs).get());
+            if rds.ps != rds_ps {
+                rds_ps = rds.ps;
+                display.draw_rds_ps(&rds_ps);
+            }
+            if let Some(pi) = rds.pi {
+                let rt = core::str::from_utf8(&rds.radio_text).unwrap_or("");
+                info!("RDS PI: {=u16:04x} RT: {=str}", pi, rt);
+            }
+            if let Some(ct) = rds.clock {
+                let (y, m, d) = ct.date();
+                info!(
+                    "RDS CT: {}-{}-{} {}:{} UTC, offset {} x 30min",
+                    y, m, d, ct.hour, ct.minute, ct.offset
+                );
+            }
             t = tt;
         }
     }
 }
+
+// what core0 keeps for each demod channel
+#[derive(Copy, Clone)]
+struct ChannelState {
+    // demod tune [Hz]
+    tune: i32,
+    method: DemodMethod,
+    // carrier squelch level [dBFS]; off below the minimum
+    squelch: i32,
+}
+
+impl ChannelState {
+    fn new() -> Self {
+        Self {
+            tune: 0,
+            method: DemodMethod::AM,
+            squelch: Squelch::MIN_CARRIER_LEVEL - 1,
+        }
+    }
+}
+
+// everything calibrated goes to flash at once
+fn save_calibration(demod: &mut demod::DemodTask, iq: &IqCorrection, ref_ppb: i32) {
+    let (iq_phase, iq_gain) = iq.coefficients();
+    demod.pause(|| {
+        storage::save(&Calibration {
+            iq_phase,
+            iq_gain,
+            ref_ppb,
+        })
+    });
+}
+
+// carrier off the LO in auto calibration [Hz]
+const CAL_OFFSET: i32 = 10_000;
+// spectra let go after tuning, and averaged
+const CAL_SETTLE: u32 = 4;
+const CAL_FRAMES: u32 = 16;
+// blocks the carrier phase is followed, some seconds
+const CAL_BLOCKS: u32 = 1200;
+
+// carrier measurement in progress
+struct CalRun<const N: usize> {
+    search: CarrierSearch<N>,
+    track: Option<PhaseTrack>,
+    // [Hz]
+    lo: u32,
+    // carrier off the achieved LO, if the reference were right [mHz]
+    expected: i64,
+    // auto calibration: LO to go back to, and the result waits for confirmation
+    restore: Option<u32>,
+}
+
+// tunes the LO and shows the freq; returns the achieved one [mHz]
+fn tune<A: hal::timer::Alarm>(
+    clockctl: &mut ClockCtl<A>,
+    display: &mut DispManager,
+    f: u32,
+) -> Option<u64> {
+    match clockctl.tune(hal::fugit::HertzU32::Hz(f)) {
+        Err(e) => {
+            info!("Failed to tune: {}", e);
+            None
+        }
+        Ok(achieved) => {
+            info!("tuned to {} mHz", achieved);
+            display.draw_freq(f, achieved);
+            Some(achieved)
+        }
+    }
+}
+
+// the achieved freq moves a little with the reference
+fn set_ppb<A: hal::timer::Alarm>(
+    clockctl: &mut ClockCtl<A>,
+    display: &mut DispManager,
+    settings: &mut Settings,
+) {
+    settings.ref_ppb = settings.ref_ppb.clamp(-MAX_PPB, MAX_PPB);
+    match clockctl.set_ppb(settings.ref_ppb) {
+        Err(e) => info!("Failed to tune: {}", e),
+        Ok(achieved) => display.draw_freq(clockctl.get_current_freq().to_Hz(), achieved),
+    }
+}
+
+fn squelch_level(level: i32) -> Option<i32> {
+    (level >= Squelch::MIN_CARRIER_LEVEL).then_some(level)
+}
+
+// beat note offset shown next to the demod freq
+fn bfo_pitch(method: DemodMethod, settings: &Settings) -> Option<u32> {
+    matches!(method, DemodMethod::CW).then_some(settings.cw_pitch)
+}
+
+// demod freq of the selected channel, and the marker of the other one while dual watching
+fn draw_tune(display: &mut DispManager, channels: &[ChannelState], settings: &Settings) {
+    let c = &channels[settings.channel];
+    let other = (settings.route != AudioRoute::Single)
+        .then(|| channels[(settings.channel + 1) % CHANNELS].tune);
+    display.draw_demod_freq(c.tune, bfo_pitch(c.method, settings), other);
+}
+
+// everything of the selected channel; status is drawn as it comes
+fn draw_channel(display: &mut DispManager, channels: &[ChannelState], settings: &Settings) {
+    let c = &channels[settings.channel];
+    display.draw_channel((settings.route != AudioRoute::Single).then_some(settings.channel));
+    draw_tune(display, channels, settings);
+    display.draw_method(c.method);
+    display.draw_lock(c.method, false);
+    display.draw_squelch(squelch_level(c.squelch));
+    display.draw_squelch_open(true);
+    display.draw_agc_gain(None);
+}
@@ -0,0 +1,319 @@
+// option menu: settings which don't have their own place on the screen.
+// one item is shown at a time; cursor selects the item, then its value.
+
+use crate::clockctl::MAX_PPB;
+use crate::core::{demod::CHANNELS, protocol::AudioRoute};
+use crate::sdr::{
+    agc::AgcPreset,
+    blanker::NoiseBlanker,
+    channel::Channel,
+    demod::{CwDemod, DemodMethod, NfmDemod, SamSideband, SsbDemod, StereoDecoder, MODES},
+    iq::IqMode,
+    nr::NrLevel,
+    refcal::{self, CalFault},
+    squelch::Squelch,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    Agc,
+    SsbBandwidth,
+    CwPitch,
+    CwWidth,
+    SamSideband,
+    FmDeemphasis,
+    NfmChannel,
+    NoiseSquelch,
+    DualWatch,
+    Channel,
+    NoiseBlanker,
+    BlankerWidth,
+    NoiseReduction,
+    Notch,
+    AutoNotch,
+    IqCorrection,
+    RefPpb,
+    ZeroBeat,
+    CalCarrier,
+    AutoCal,
+    // result of AutoCal; not in ALL, shown only after a run
+    CalConfirm,
+    // why a run is not taken; likewise
+    CalFailed,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 20] = [
+        MenuItem::Agc,
+        MenuItem::SsbBandwidth,
+        MenuItem::CwPitch,
+        MenuItem::CwWidth,
+        MenuItem::SamSideband,
+        MenuItem::FmDeemphasis,
+        MenuItem::NfmChannel,
+        MenuItem::NoiseSquelch,
+        MenuItem::DualWatch,
+        MenuItem::Channel,
+        MenuItem::NoiseBlanker,
+        MenuItem::BlankerWidth,
+        MenuItem::NoiseReduction,
+        MenuItem::Notch,
+        MenuItem::AutoNotch,
+        MenuItem::IqCorrection,
+        MenuItem::RefPpb,
+        MenuItem::ZeroBeat,
+        MenuItem::CalCarrier,
+        MenuItem::AutoCal,
+    ];
+
+    // padded to the same width
+    pub fn name(&self) -> &'static [u8] {
+        match self {
+            MenuItem::Agc => b"AGC    ",
+            MenuItem::SsbBandwidth => b"SSB BW ",
+            MenuItem::CwPitch => b"CW BFO ",
+            MenuItem::CwWidth => b"CW BW  ",
+            MenuItem::SamSideband => b"SAM SB ",
+            MenuItem::FmDeemphasis => b"FM DE  ",
+            MenuItem::NfmChannel => b"NFM CH ",
+            MenuItem::NoiseSquelch => b"FM SQL ",
+            MenuItem::DualWatch => b"DUAL   ",
+            MenuItem::Channel => b"CHANNEL",
+            MenuItem::NoiseBlanker => b"NB     ",
+            MenuItem::BlankerWidth => b"NB WID ",
+            MenuItem::NoiseReduction => b"NR     ",
+            MenuItem::Notch => b"NOTCH  ",
+            MenuItem::AutoNotch => b"ANF    ",
+            MenuItem::IqCorrection => b"IQ CAL ",
+            MenuItem::RefPpb => b"REF PPB",
+            MenuItem::ZeroBeat => b"ZEROBT ",
+            MenuItem::CalCarrier => b"CAL REF",
+            MenuItem::AutoCal => b"AUTOCAL",
+            MenuItem::CalConfirm => b"APPLY? ",
+            MenuItem::CalFailed => b"FAILED ",
+        }
+    }
+
+    pub fn rotate(self, rot: i32) -> Self {
+        let i = (self as i32 + rot).rem_euclid(Self::ALL.len() as i32);
+        Self::ALL[i as usize]
+    }
+}
+
+// [Hz] per click
+const NOTCH_STEP: i32 = 20;
+// [ppb] per click; 1Hz at 100MHz
+const PPB_STEP: i32 = 10;
+
+pub struct Settings {
+    // for each method
+    agc: [AgcPreset; DemodMethod::METHOD_COUNT as usize],
+    method: DemodMethod,
+    pub ssb_bandwidth: u32,
+    pub cw_pitch: u32,
+    pub cw_width: u32,
+    pub sam_sideband: SamSideband,
+    pub fm_deemphasis: u32,
+    pub nfm_channel: u32,
+    // required quieting [dB]; 0 is off
+    pub noise_squelch: u32,
+    pub route: AudioRoute,
+    // threshold ratio of the noise blanker; 0 is off
+    pub nb_threshold: u32,
+    // [us]
+    pub nb_width: u32,
+    // audio noise reduction of all channels
+    pub nr: NrLevel,
+    // manual notch [Hz]; 0 is off
+    pub notch: u32,
+    pub auto_notch: bool,
+    // coefficients are saved on entering Hold
+    pub iq: IqMode,
+    // reference error [ppb]; saved on leaving the item, or set by zero beat
+    pub ref_ppb: i32,
+    // carrier for AutoCal: 0 is the tuned freq, then refcal::STANDARDS
+    pub cal_carrier: usize,
+    pub calibrating: bool,
+    // measured by AutoCal [ppb]
+    pub cal_error: i32,
+    pub cal_fault: CalFault,
+    // demod channel under control; only A unless dual watching
+    pub channel: usize,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            agc: MODES.map(|m| m.agc),
+            method: DemodMethod::AM,
+            ssb_bandwidth: SsbDemod::DEFAULT_BANDWIDTH,
+            cw_pitch: CwDemod::DEFAULT_PITCH,
+            cw_width: CwDemod::WIDTHS[1],
+            sam_sideband: SamSideband::Double,
+            fm_deemphasis: StereoDecoder::DEFAULT_DEEMPHASIS,
+            nfm_channel: NfmDemod::CHANNELS[0],
+            noise_squelch: 0,
+            route: AudioRoute::Single,
+            nb_threshold: 0,
+            nb_width: NoiseBlanker::DEFAULT_WIDTH,
+            nr: NrLevel::Off,
+            notch: 0,
+            auto_notch: false,
+            iq: IqMode::Track,
+            ref_ppb: 0,
+            cal_carrier: 0,
+            calibrating: false,
+            cal_error: 0,
+            cal_fault: CalFault::NoCarrier,
+            channel: 0,
+        }
+    }
+
+    // select method for per-method settings
+    pub fn set_method(&mut self, method: DemodMethod) {
+        self.method = method;
+    }
+
+    pub fn agc(&self) -> AgcPreset {
+        self.agc[self.method as usize]
+    }
+
+    pub fn value(&self, item: MenuItem) -> i32 {
+        match item {
+            MenuItem::Agc => self.agc() as i32,
+            MenuItem::SsbBandwidth => self.ssb_bandwidth as i32,
+            MenuItem::CwPitch => self.cw_pitch as i32,
+            MenuItem::CwWidth => self.cw_width as i32,
+            MenuItem::SamSideband => self.sam_sideband as i32,
+            MenuItem::FmDeemphasis => self.fm_deemphasis as i32,
+            MenuItem::NfmChannel => self.nfm_channel as i32,
+            MenuItem::NoiseSquelch => self.noise_squelch as i32,
+            MenuItem::DualWatch => self.route as i32,
+            MenuItem::Channel => self.channel as i32,
+            MenuItem::NoiseBlanker => self.nb_threshold as i32,
+            MenuItem::BlankerWidth => self.nb_width as i32,
+            MenuItem::NoiseReduction => self.nr as i32,
+            MenuItem::Notch => self.notch as i32,
+            MenuItem::AutoNotch => self.auto_notch as i32,
+            MenuItem::IqCorrection => self.iq as i32,
+            MenuItem::RefPpb | MenuItem::ZeroBeat => self.ref_ppb,
+            MenuItem::CalCarrier => self.cal_carrier as i32,
+            MenuItem::AutoCal => self.calibrating as i32,
+            MenuItem::CalConfirm => self.cal_error,
+            MenuItem::CalFailed => self.cal_fault as i32,
+        }
+    }
+
+    pub fn adjust(&mut self, item: MenuItem, rot: i32) {
+        match item {
+            MenuItem::Agc => {
+                let all = &AgcPreset::ALL;
+                let i = (self.agc() as i32 + rot).rem_euclid(all.len() as i32);
+                self.agc[self.method as usize] = all[i as usize];
+            }
+            MenuItem::SsbBandwidth => {
+                self.ssb_bandwidth = (self.ssb_bandwidth as i32 + rot * 100).clamp(
+                    SsbDemod::MIN_BANDWIDTH as i32,
+                    SsbDemod::MAX_BANDWIDTH as i32,
+                ) as u32;
+            }
+            MenuItem::CwPitch => {
+                self.cw_pitch = (self.cw_pitch as i32 + rot * 10)
+                    .clamp(CwDemod::MIN_PITCH as i32, CwDemod::MAX_PITCH as i32)
+                    as u32;
+            }
+            MenuItem::CwWidth => {
+                let w = &CwDemod::WIDTHS;
+                let i = w.iter().position(|&x| x == self.cw_width).unwrap_or(0) as i32;
+                self.cw_width = w[(i + rot).rem_euclid(w.len() as i32) as usize];
+            }
+            MenuItem::SamSideband => {
+                let all = &SamSideband::ALL;
+                let i = (self.sam_sideband as i32 + rot).rem_euclid(all.len() as i32);
+                self.sam_sideband = all[i as usize];
+            }
+            MenuItem::FmDeemphasis => {
+                let d = &StereoDecoder::DEEMPHASIS;
+                let i = d.iter().position(|&x| x == self.fm_deemphasis).unwrap_or(0) as i32;
+                self.fm_deemphasis = d[(i + rot).rem_euclid(d.len() as i32) as usize];
+            }
+            MenuItem::NfmChannel => {
+                let c = &NfmDemod::CHANNELS;
+                let i = c.iter().position(|&x| x == self.nfm_channel).unwrap_or(0) as i32;
+                self.nfm_channel = c[(i + rot).rem_euclid(c.len() as i32) as usize];
+            }
+            MenuItem::NoiseSquelch => {
+                self.noise_squelch =
+                    (self.noise_squelch as i32 + rot).clamp(0, Squelch::MAX_NOISE_LEVEL) as u32;
+            }
+            MenuItem::DualWatch => {
+                let all = &AudioRoute::ALL;
+                let i = (self.route as i32 + rot).rem_euclid(all.len() as i32);
+                self.route = all[i as usize];
+                if self.route == AudioRoute::Single {
+                    self.channel = 0;
+                }
+            }
+            MenuItem::Channel => {
+                if self.route != AudioRoute::Single {
+                    self.channel = (self.channel as i32 + rot).rem_euclid(CHANNELS as i32) as usize;
+                }
+            }
+            MenuItem::NoiseBlanker => {
+                // off just below the minimum
+                let min = NoiseBlanker::MIN_THRESHOLD as i32;
+                let t = if self.nb_threshold == 0 {
+                    min - 1
+                } else {
+                    self.nb_threshold as i32
+                };
+                let t = (t + rot).clamp(min - 1, NoiseBlanker::MAX_THRESHOLD as i32);
+                self.nb_threshold = if t < min { 0 } else { t as u32 };
+            }
+            MenuItem::BlankerWidth => {
+                self.nb_width = (self.nb_width as i32 + rot * 10).clamp(
+                    NoiseBlanker::MIN_WIDTH as i32,
+                    NoiseBlanker::MAX_WIDTH as i32,
+                ) as u32;
+            }
+            MenuItem::NoiseReduction => {
+                let all = &NrLevel::ALL;
+                let i = (self.nr as i32 + rot).rem_euclid(all.len() as i32);
+                self.nr = all[i as usize];
+            }
+            MenuItem::Notch => {
+                // off just below the minimum
+                let min = Channel::MIN_NOTCH as i32;
+                let f = if self.notch == 0 {
+                    min - NOTCH_STEP
+                } else {
+                    self.notch as i32
+                };
+                let f = (f + rot * NOTCH_STEP).clamp(min - NOTCH_STEP, Channel::MAX_NOTCH as i32);
+                self.notch = if f < min { 0 } else { f as u32 };
+            }
+            MenuItem::AutoNotch => self.auto_notch = (self.auto_notch as i32 + rot) & 1 != 0,
+            MenuItem::IqCorrection => {
+                let all = &IqMode::ALL;
+                let i = (self.iq as i32 + rot).rem_euclid(all.len() as i32);
+                self.iq = all[i as usize];
+            }
+            MenuItem::RefPpb => {
+                self.ref_ppb = (self.ref_ppb + rot * PPB_STEP).clamp(-MAX_PPB, MAX_PPB);
+            }
+            MenuItem::CalCarrier => {
+                let n = refcal::STANDARDS.len() as i32 + 1;
+                self.cal_carrier = (self.cal_carrier as i32 + rot).rem_euclid(n) as usize;
+            }
+            // a measurement is started, or its result taken, instead
+            MenuItem::ZeroBeat | MenuItem::AutoCal | MenuItem::CalConfirm | MenuItem::CalFailed => {
+            }
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
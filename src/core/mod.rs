@@ -3,4 +3,6 @@ pub use main::main;
 mod demod;
 mod display;
 mod dma;
+mod menu;
+mod protocol;
 mod usb;
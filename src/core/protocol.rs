@@ -0,0 +1,205 @@
+// messages between core0 and core1 over the SIO FIFO
+//
+// core0 -> core1: a word with bit31 clear is a pointer to DemodBuffer, otherwise a Command.
+// core1 -> core0: Events only.
+// both are one word; id in bits 24..31 and payload in the lower 24 bits.
+// Command ids are in 0x80..0x8f, then in 0xc0..0xcf.
+// bits 28..29 of the id address the demod channels: a mask for Commands, an index for Events.
+
+use crate::sdr::{
+    agc::AgcPreset,
+    demod::{DemodMethod, SamSideband},
+    nr::NrLevel,
+    squelch::Squelch,
+};
+
+// where the audio of the demod channels goes
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum AudioRoute {
+    // channel A only; B is not processed
+    Single,
+    // A on left, B on right
+    Split,
+    // both on both sides
+    Mix,
+}
+
+impl AudioRoute {
+    pub const ALL: [AudioRoute; 3] = [AudioRoute::Single, AudioRoute::Split, AudioRoute::Mix];
+}
+
+#[derive(Copy, Clone)]
+pub enum Command {
+    // demod tune [Hz]
+    Tune(i32),
+    Method(DemodMethod),
+    // audio bandwidth of SSB [Hz]
+    SsbBandwidth(u32),
+    // BFO pitch of CW [Hz]
+    CwPitch(u32),
+    // audio bandwidth of CW [Hz]
+    CwWidth(u32),
+    SamSideband(SamSideband),
+    // de-emphasis time constant of FM [us]
+    FmDeemphasis(u32),
+    // forget RDS data, e.g. on station change
+    ResetRds,
+    // channel spacing of NFM [Hz]
+    NfmChannel(u32),
+    // carrier squelch level [dBFS]
+    Squelch(Option<i32>),
+    // required quieting of FM noise squelch [dB]
+    NoiseSquelch(Option<u32>),
+    // audio AGC of the current method
+    Agc(AgcPreset),
+    Route(AudioRoute),
+    // threshold of the noise blanker, as a ratio to the average level
+    NoiseBlanker(Option<u32>),
+    // blanked span around an impulse [us]
+    BlankerWidth(u32),
+    // audio noise reduction
+    NoiseReduction(NrLevel),
+    // manual audio notch [Hz]
+    Notch(Option<u32>),
+    AutoNotch(bool),
+    // core1 waits in RAM until a word comes, answering Event::Paused; see DemodTask::pause
+    Pause,
+}
+
+impl Command {
+    // `channels`: bit n for channel n
+    pub fn encode(&self, channels: u8) -> u32 {
+        let (id, v) = match *self {
+            Command::Tune(f) => (0x80, f as u32),
+            Command::Method(m) => (0x81, m as u32),
+            Command::SsbBandwidth(b) => (0x82, b),
+            Command::CwPitch(p) => (0x83, p),
+            Command::CwWidth(w) => (0x84, w),
+            Command::SamSideband(s) => (0x85, s as u32),
+            Command::FmDeemphasis(t) => (0x86, t),
+            Command::ResetRds => (0x87, 0),
+            Command::NfmChannel(c) => (0x88, c),
+            Command::Squelch(l) => (
+                0x89,
+                l.unwrap_or(i32::MIN).max(Squelch::MIN_CARRIER_LEVEL - 1) as u32,
+            ),
+            Command::NoiseSquelch(l) => (0x8a, l.unwrap_or(0)),
+            Command::Agc(a) => (0x8b, a as u32),
+            Command::Route(r) => (0x8c, r as u32),
+            Command::NoiseBlanker(t) => (0x8d, t.unwrap_or(0)),
+            Command::BlankerWidth(w) => (0x8e, w),
+            Command::NoiseReduction(l) => (0x8f, l as u32),
+            Command::Notch(f) => (0xc0, f.unwrap_or(0)),
+            Command::AutoNotch(on) => (0xc1, on as u32),
+            Command::Pause => (0xc2, 0),
+        };
+        (id << 24) | ((channels as u32 & 3) << 28) | (v & 0xffffff)
+    }
+
+    // channel mask and command; None for pointers and unknown words
+    pub fn decode(w: u32) -> Option<(u8, Self)> {
+        if w & 0x8000_0000 == 0 {
+            return None;
+        }
+        let v = w & 0xffffff;
+        let s = ((w << 8) as i32) >> 8; // sign extend
+        let channels = ((w >> 28) & 3) as u8;
+        let command = match (w >> 24) & 0xcf {
+            0x80 => Command::Tune(s),
+            0x81 => Command::Method(DemodMethod::from_u8(u8::try_from(v).ok()?)?),
+            0x82 => Command::SsbBandwidth(v),
+            0x83 => Command::CwPitch(v),
+            0x84 => Command::CwWidth(v),
+            0x85 => Command::SamSideband(*SamSideband::ALL.get(v as usize)?),
+            0x86 => Command::FmDeemphasis(v),
+            0x87 => Command::ResetRds,
+            0x88 => Command::NfmChannel(v),
+            0x89 => Command::Squelch((s >= Squelch::MIN_CARRIER_LEVEL).then_some(s)),
+            0x8a => Command::NoiseSquelch((v > 0).then_some(v)),
+            0x8b => Command::Agc(*AgcPreset::ALL.get(v as usize)?),
+            0x8c => Command::Route(*AudioRoute::ALL.get(v as usize)?),
+            0x8d => Command::NoiseBlanker((v > 0).then_some(v)),
+            0x8e => Command::BlankerWidth(v),
+            0x8f => Command::NoiseReduction(*NrLevel::ALL.get(v as usize)?),
+            0xc0 => Command::Notch((v > 0).then_some(v)),
+            0xc1 => Command::AutoNotch(v != 0),
+            0xc2 => Command::Pause,
+            _ => return None,
+        };
+        Some((channels, command))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Event {
+    // number of commands processed so far, wrapping in 24 bits.
+    // cumulative, so that a dropped one is covered by the next
+    Ack(u32),
+    State {
+        channel: u8,
+        // carrier lock of synchronous AM, or pilot lock of FM stereo
+        locked: bool,
+        // false while audio is muted by squelch
+        squelch_open: bool,
+        // audio AGC gain [dB]
+        agc_gain: i8,
+    },
+    Level {
+        channel: u8,
+        // channel power [Q8 dBFS]
+        level: i32,
+    },
+    Load {
+        // longest processing time of a block in the last report period [us]
+        time: u16,
+        // number of times audio output ran dry, wrapping
+        overruns: u8,
+    },
+    // core1 is off flash, in answer to Command::Pause
+    Paused,
+}
+
+impl Event {
+    pub fn encode(&self) -> u32 {
+        let (id, v) = match *self {
+            Event::Ack(n) => (0x80, n),
+            Event::State {
+                channel,
+                locked,
+                squelch_open,
+                agc_gain,
+            } => (
+                0x81 | ((channel as u32 & 3) << 4),
+                ((squelch_open as u32) << 9) | ((locked as u32) << 8) | agc_gain as u8 as u32,
+            ),
+            Event::Level { channel, level } => (0x82 | ((channel as u32 & 3) << 4), level as u32),
+            Event::Load { time, overruns } => (0x83, ((overruns as u32) << 16) | time as u32),
+            Event::Paused => (0x84, 0),
+        };
+        (id << 24) | (v & 0xffffff)
+    }
+
+    pub fn decode(w: u32) -> Option<Self> {
+        let v = w & 0xffffff;
+        let channel = ((w >> 28) & 3) as u8;
+        Some(match (w >> 24) & 0x8f {
+            0x80 => Event::Ack(v),
+            0x81 => Event::State {
+                channel,
+                locked: v & (1 << 8) != 0,
+                squelch_open: v & (1 << 9) != 0,
+                agc_gain: v as u8 as i8,
+            },
+            0x82 => Event::Level {
+                channel,
+                level: ((w << 8) as i32) >> 8,
+            },
+            0x83 => Event::Load {
+                time: v as u16,
+                overruns: (v >> 16) as u8,
+            },
+            0x84 => Event::Paused,
+            _ => return None,
+        })
+    }
+}
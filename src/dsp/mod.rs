@@ -1,6 +0,0 @@
-mod complex;
-pub mod fft;
-mod number;
-
-pub use complex::DSPComplex;
-pub use number::DSPNum;
@@ -1,7 +1,5 @@
 #![no_std]
 
-pub const SAMPLE_RATE: usize = 192_000;
-
 use defmt_rtt as _;
 #[cfg(debug_assertions)]
 use panic_probe as _;
@@ -12,6 +10,8 @@ extern crate panic_halt;
 pub use rp2040_hal as hal;
 pub use rp_pico as bsp;
 
+pub use fuwasdr_dsp::{dsp, sdr, SAMPLE_RATE};
+
 pub mod board;
 
 pub mod clockctl;
@@ -19,7 +19,6 @@ pub mod codec;
 pub mod control;
 pub mod core;
 pub mod display;
-pub mod dsp;
 pub mod i2c;
-pub mod sdr;
+pub mod storage;
 pub mod util;
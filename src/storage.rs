@@ -0,0 +1,106 @@
+// calibration kept over power cycles, in a flash sector of its own; see memory.x
+
+use crate::hal::rom_data;
+
+// from the start of flash
+const OFFSET: u32 = 0x1cb000;
+const XIP_BASE: u32 = 0x1000_0000;
+const SECTOR_SIZE: u32 = 4096;
+const PAGE_SIZE: usize = 256;
+// "FWS" and the layout version
+const MAGIC: u32 = 0x4657_5302;
+
+#[derive(Copy, Clone)]
+pub struct Calibration {
+    // IqCorrection coefficients, Q24
+    pub iq_phase: i32,
+    pub iq_gain: i32,
+    // reference error, see ClockCtl::set_ppb
+    pub ref_ppb: i32,
+}
+
+impl Calibration {
+    const WORDS: usize = 3;
+
+    fn to_words(self) -> [u32; Self::WORDS] {
+        [
+            self.iq_phase as u32,
+            self.iq_gain as u32,
+            self.ref_ppb as u32,
+        ]
+    }
+
+    fn from_words(w: &[u32]) -> Self {
+        Self {
+            iq_phase: w[0] as i32,
+            iq_gain: w[1] as i32,
+            ref_ppb: w[2] as i32,
+        }
+    }
+}
+
+// stored as MAGIC, words, then their sum
+fn checksum(w: &[u32]) -> u32 {
+    w.iter().fold(0u32, |a, &x| a.wrapping_add(x))
+}
+
+pub fn load() -> Option<Calibration> {
+    let p = (XIP_BASE + OFFSET) as *const u32;
+    let mut w = [0u32; Calibration::WORDS + 2];
+    for (i, x) in w.iter_mut().enumerate() {
+        *x = unsafe { p.add(i).read_volatile() };
+    }
+    let n = Calibration::WORDS + 1;
+    if w[0] != MAGIC || checksum(&w[..n]) != w[n] {
+        return None;
+    }
+    Some(Calibration::from_words(&w[1..n]))
+}
+
+// core1 must not run from flash meanwhile; see DemodTask::pause
+pub fn save(cal: &Calibration) {
+    let mut page = [0xffu32; PAGE_SIZE / 4];
+    let n = Calibration::WORDS + 1;
+    page[0] = MAGIC;
+    page[1..n].copy_from_slice(&cal.to_words());
+    page[n] = checksum(&page[..n]);
+
+    // boot2 brings back the fast XIP mode afterwards; it must run from RAM too
+    let mut boot2 = [0u32; PAGE_SIZE / 4];
+    for (i, x) in boot2.iter_mut().enumerate() {
+        *x = unsafe { (XIP_BASE as *const u32).add(i).read_volatile() };
+    }
+    // ROM lookups run from flash; resolve them before
+    let f = unsafe {
+        RomFns {
+            connect: core::mem::transmute(rom_data::connect_internal_flash::ptr()),
+            exit_xip: core::mem::transmute(rom_data::flash_exit_xip::ptr()),
+            erase: core::mem::transmute(rom_data::flash_range_erase::ptr()),
+            program: core::mem::transmute(rom_data::flash_range_program::ptr()),
+            flush: core::mem::transmute(rom_data::flash_flush_cache::ptr()),
+            boot2: core::mem::transmute(boot2.as_ptr() as usize + 1),
+        }
+    };
+    cortex_m::interrupt::free(|_| unsafe { write_sector(&f, page.as_ptr() as *const u8) });
+}
+
+struct RomFns {
+    connect: extern "C" fn(),
+    exit_xip: extern "C" fn(),
+    erase: extern "C" fn(u32, usize, u32, u8),
+    program: extern "C" fn(u32, *const u8, usize),
+    flush: extern "C" fn(),
+    boot2: extern "C" fn(),
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn write_sector(f: &RomFns, page: *const u8) {
+    (f.connect)();
+    (f.exit_xip)();
+    // 0x20: 4k sector erase
+    (f.erase)(OFFSET, SECTOR_SIZE as usize, SECTOR_SIZE, 0x20);
+    (f.program)(OFFSET, page, PAGE_SIZE);
+    (f.flush)();
+    (f.boot2)();
+}
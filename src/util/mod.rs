@@ -1 +1 @@
-pub mod gcd;
+pub mod rational;
@@ -0,0 +1,26 @@
+// best rational approximation b / c of num / den (num < den) with c <= max_den,
+// by continued fractions; the last term may be a semiconvergent
+pub fn best_approx(num: u64, den: u64, max_den: u32) -> (u32, u32) {
+    let max_den = max_den as u64;
+    // convergents h / k
+    let (mut h0, mut h1, mut k0, mut k1) = (0u64, 1u64, 1u64, 0u64);
+    let (mut n, mut d) = (num, den);
+    while d != 0 {
+        let a = n / d;
+        let k2 = a * k1 + k0;
+        if k2 > max_den {
+            let t = (max_den - k0) / k1;
+            let (hs, ks) = (t * h1 + h0, t * k1 + k0);
+            // |num / den - h / k| compared as |num * k - h * den| / k
+            let err = |h: u64, k: u64| (num * k).abs_diff(h * den);
+            if err(hs, ks) * k1 < err(h1, k1) * ks {
+                return (hs as u32, ks as u32);
+            }
+            return (h1 as u32, k1 as u32);
+        }
+        (h0, h1) = (h1, a * h1 + h0);
+        (k0, k1) = (k1, k2);
+        (n, d) = (d, n - a * d);
+    }
+    (h1 as u32, k1 as u32)
+}